@@ -1,27 +1,161 @@
-use anyhow::{Context, Result};
+use std::{error::Error, fmt};
+
+use anyhow::{bail, Context, Result};
+use tonic::{transport::Channel, Code};
 
 use crate::server::grpc::api::{
-    spotifatius_client::SpotifatiusClient, ToggleLikedRequest,
+    spotifatius_client::SpotifatiusClient, GetCurrentRequest, IsLikedRequest,
+    IsLikedResponse, MonitorResponse, ServerInfoRequest, ServerInfoResponse,
+    SetLikedRequest, ShutdownRequest, ToggleLikedRequest, VersionRequest,
+    VersionResponse,
 };
-use crate::shared::consts::ADDRESS;
+use crate::shared::config::MessagesConfig;
+use crate::shared::consts::{ADDRESS, PROTOCOL_VERSION};
+
+/// The running server speaks a different protocol version, which happens when
+/// it's still running an older or newer spotifatius after an upgrade.
+#[derive(Debug)]
+pub struct ProtocolMismatch {
+    /// Default for servers from before the version handshake.
+    pub server: VersionResponse,
+}
+
+impl fmt::Display for ProtocolMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let server_version = if self.server.version.is_empty() {
+            "an older version".to_string()
+        } else {
+            self.server.version.clone()
+        };
+        write!(
+            f,
+            "The running server is spotifatius {server_version} using protocol {}, but this is spotifatius {} using protocol {PROTOCOL_VERSION}. Restart it with `spotifatius restart`.",
+            self.server.protocol_version,
+            env!("CARGO_PKG_VERSION"),
+        )
+    }
+}
+
+impl Error for ProtocolMismatch {}
+
+/// Whether the error means the server can't be used, in which case commands
+/// can fall back to asking Spotify directly.
+pub fn server_unavailable(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<tonic::transport::Error>().is_some()
+        || err.downcast_ref::<ProtocolMismatch>().is_some()
+}
 
 pub struct Service {}
 
 impl Service {
-    pub async fn toggle_liked() -> Result<()> {
-        let mut client =
-            SpotifatiusClient::connect(format!("http://{ADDRESS}")).await
-            .context("Could not connect to monitor instance, make sure there is one running")?;
-        let request = tonic::Request::new(ToggleLikedRequest {});
-        let response = client.toggle_liked(request).await?;
-        println!(
-            "{}",
-            if response.get_ref().is_liked {
-                "Added to library!"
-            } else {
-                "Removed from library!"
+    async fn connect_unchecked() -> Result<SpotifatiusClient<Channel>> {
+        SpotifatiusClient::connect(format!("http://{ADDRESS}"))
+            .await
+            .context("Could not connect to monitor instance, make sure there is one running")
+    }
+
+    /// Connect to the server and check it speaks the same protocol version.
+    pub async fn connect() -> Result<SpotifatiusClient<Channel>> {
+        Ok(Service::handshake().await?.0)
+    }
+
+    async fn handshake() -> Result<(SpotifatiusClient<Channel>, VersionResponse)>
+    {
+        let mut client = Service::connect_unchecked().await?;
+        let server = match client.version(VersionRequest {}).await {
+            Ok(response) => response.into_inner(),
+            // Servers from before the handshake don't implement it.
+            Err(status) if status.code() == Code::Unimplemented => {
+                VersionResponse::default()
             }
-        );
+            Err(status) => return Err(status.into()),
+        };
+        if server.protocol_version != PROTOCOL_VERSION {
+            return Err(ProtocolMismatch { server }.into());
+        }
+        Ok((client, server))
+    }
+
+    pub async fn toggle_liked(messages: &MessagesConfig) -> Result<()> {
+        let mut client = Service::connect().await?;
+        let request = tonic::Request::new(ToggleLikedRequest {});
+        let response = client.toggle_liked(request).await?.into_inner();
+        let message = if response.is_liked {
+            &messages.added
+        } else {
+            &messages.removed
+        };
+        print_message(message, response.pending, messages);
         Ok(())
     }
+
+    /// Like or unlike the given or current track, returns whether the liked
+    /// state changed.
+    pub async fn set_liked(
+        track_id: Option<String>,
+        liked: bool,
+        messages: &MessagesConfig,
+    ) -> Result<bool> {
+        let mut client = Service::connect().await?;
+        let request = tonic::Request::new(SetLikedRequest { track_id });
+        let response = if liked {
+            client.like(request).await?
+        } else {
+            client.unlike(request).await?
+        };
+        let response = response.get_ref();
+        let message = match (response.changed, liked) {
+            (true, true) => &messages.added,
+            (true, false) => &messages.removed,
+            (false, true) => &messages.already_added,
+            (false, false) => &messages.not_added,
+        };
+        print_message(message, response.pending, messages);
+        Ok(response.changed)
+    }
+
+    pub async fn is_liked(track_id: Option<String>) -> Result<IsLikedResponse> {
+        let mut client = Service::connect().await?;
+        let request = tonic::Request::new(IsLikedRequest { track_id });
+        Ok(client.is_liked(request).await?.into_inner())
+    }
+
+    pub async fn get_current() -> Result<MonitorResponse> {
+        let mut client = Service::connect().await?;
+        let request = tonic::Request::new(GetCurrentRequest {});
+        Ok(client.get_current(request).await?.into_inner())
+    }
+
+    pub async fn server_info() -> Result<ServerInfoResponse> {
+        let mut client = Service::connect().await?;
+        let request = tonic::Request::new(ServerInfoRequest {});
+        Ok(client.server_info(request).await?.into_inner())
+    }
+
+    pub async fn version() -> Result<VersionResponse> {
+        Ok(Service::handshake().await?.1)
+    }
+
+    /// Ask the server to stop, returns its PID.
+    pub async fn shutdown() -> Result<u32> {
+        // The protocol version isn't checked so older servers can be stopped
+        // after an upgrade.
+        let mut client = Service::connect_unchecked().await?;
+        let request = tonic::Request::new(ShutdownRequest {});
+        match client.shutdown(request).await {
+            Ok(response) => Ok(response.into_inner().pid),
+            Err(status) if status.code() == Code::Unimplemented => bail!(
+                "The running server doesn't support stopping, close the process running it instead"
+            ),
+            Err(status) => Err(status.into()),
+        }
+    }
+}
+
+fn print_message(message: &str, pending: bool, messages: &MessagesConfig) {
+    if pending {
+        println!("{message} {}", messages.pending);
+    } else {
+        println!("{message}");
+    }
 }
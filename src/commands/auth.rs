@@ -0,0 +1,156 @@
+use std::{
+    path::PathBuf,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    time::timeout,
+};
+use tracing::{debug, warn};
+
+use crate::{
+    server::spotify_client::SpotifyClient,
+    shared::config::{get_config, DEFAULT_CONFIG_PATH},
+};
+
+const RESPONSE_BODY: &str =
+    "Spotifatius has been authorized, you can close this tab now.";
+/// How long a connection gets to send its request, browsers also open
+/// connections in advance that may never send one.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Authorize spotifatius to access your Spotify library.
+///
+/// This opens the authorization page in your browser and listens on the
+/// redirect URI to capture the result, after which the token is cached so
+/// other commands can use it.
+#[derive(Parser)]
+pub struct Auth {
+    /// Config file path.
+    #[clap(
+        short,
+        long,
+        parse(from_os_str),
+        default_value = DEFAULT_CONFIG_PATH
+    )]
+    pub config: PathBuf,
+}
+
+pub async fn run(opts: Auth) -> Result<()> {
+    let config = get_config(opts.config)?;
+    let mut spotify = SpotifyClient::new(&config.spotify)?;
+    let url = spotify.get_authorize_url()?;
+    let redirect_uri = spotify.redirect_uri().to_string();
+
+    // Listen on the host and port of the redirect URI, e.g. `127.0.0.1:8000`
+    // for `http://127.0.0.1:8000/callback`.
+    let address = redirect_uri
+        .trim_start_matches("http://")
+        .split('/')
+        .next()
+        .unwrap_or_default();
+    let listener = TcpListener::bind(address).await.with_context(|| {
+        format!("Could not listen on {address} for the redirect")
+    })?;
+
+    println!("Opening the following URL in your browser:\n{url}");
+    if let Err(err) = Command::new("xdg-open")
+        .arg(&url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        warn!("Could not open the browser: {err}");
+    }
+    println!(
+        "If you see a redirect error, please edit your Spotify app to allow the redirect URI: {redirect_uri}. This was changed in 0.3.0. See https://github.com/AndreasBackx/spotifatius/pull/9."
+    );
+
+    let code = loop {
+        let (mut stream, _) = listener.accept().await?;
+        let request_line = match read_request_line(&mut stream).await {
+            Ok(request_line) => request_line,
+            Err(err) => {
+                debug!("Ignoring connection: {err:#}");
+                continue;
+            }
+        };
+        debug!("Received redirect request: {request_line}");
+
+        // The request line looks like `GET /?code=...&state=... HTTP/1.1`.
+        let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+        match spotify.parse_response_code(&format!("http://{address}{path}")) {
+            Some(code) => {
+                // The code is all that's needed, even if the browser is gone.
+                if let Err(err) =
+                    write_response(&mut stream, "200 OK", RESPONSE_BODY).await
+                {
+                    warn!("Could not respond to the redirect: {err:#}");
+                }
+                break code;
+            }
+            None => {
+                // E.g. `?error=access_denied` when the user cancelled.
+                if let Some(error) = query_param(path, "error") {
+                    let body = format!("Authorization failed: {error}");
+                    write_response(&mut stream, "400 Bad Request", &body)
+                        .await
+                        .ok();
+                    bail!("Spotify did not authorize spotifatius: {error}");
+                }
+                if let Err(err) =
+                    write_response(&mut stream, "404 Not Found", "").await
+                {
+                    debug!("Could not respond to {path}: {err:#}");
+                }
+            }
+        }
+    };
+
+    spotify.request_token(&code).await?;
+    println!(
+        "Authorized! The token has been cached at {}",
+        spotify.cache_path().display()
+    );
+    Ok(())
+}
+
+/// Read the first line of the request, e.g. `GET /?code=... HTTP/1.1`.
+async fn read_request_line(stream: &mut TcpStream) -> Result<String> {
+    let mut request_line = String::new();
+    timeout(
+        READ_TIMEOUT,
+        BufReader::new(stream).read_line(&mut request_line),
+    )
+    .await
+    .context("Timed out waiting for the request")?
+    .context("Could not read the request")?;
+    Ok(request_line)
+}
+
+async fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    body: &str,
+) -> Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+/// Get the value of a query parameter of the request path.
+fn query_param<'a>(path: &'a str, name: &str) -> Option<&'a str> {
+    let (_, query) = path.split_once('?')?;
+    query.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key == name).then_some(value)
+    })
+}
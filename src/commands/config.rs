@@ -0,0 +1,334 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::{self, create_dir_all},
+    path::PathBuf,
+    process::exit,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+
+use crate::{
+    server::grpc::api::{MonitorResponse, Track, TrackStatus},
+    shared::{
+        config::{
+            resolve_home_path, Config, FormatsConfig, DEFAULT_CONFIG,
+            DEFAULT_CONFIG_PATH,
+        },
+        config_schema::{config_schema, unknown_keys},
+    },
+};
+
+use super::output::{OutputFormatter, OutputType};
+
+/// The placeholders that can be used in `format`.
+const PLACEHOLDERS: [&str; 7] = [
+    "artist",
+    "title",
+    "album",
+    "separator",
+    "status",
+    "liked",
+    "art_path",
+];
+/// The placeholders that can be used in the notification templates.
+const NOTIFICATION_PLACEHOLDERS: [&str; 4] =
+    ["artist", "title", "album", "message"];
+/// The classes that can be given a color in `polybar.colors`.
+const CLASSES: [&str; 9] = [
+    "playing", "paused", "stopped", "added", "removed", "liked", "ad",
+    "offline", "pending",
+];
+
+/// Check, create or describe the config file.
+#[derive(Parser)]
+pub struct ConfigCommand {
+    #[clap(subcommand)]
+    pub subcmd: ConfigSubCommand,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigSubCommand {
+    Check(Check),
+    Init(Init),
+    Schema(Schema),
+}
+
+/// Validate the config file and render the format with sample tracks.
+///
+/// Unlike the other commands, unknown keys are errors. Exits with code `1`
+/// if the config has errors.
+#[derive(Parser)]
+pub struct Check {
+    /// Config file path.
+    #[clap(
+        short,
+        long,
+        parse(from_os_str),
+        default_value = DEFAULT_CONFIG_PATH
+    )]
+    pub config: PathBuf,
+}
+
+/// Write a commented default config file.
+#[derive(Parser)]
+pub struct Init {
+    /// Config file path.
+    #[clap(
+        short,
+        long,
+        parse(from_os_str),
+        default_value = DEFAULT_CONFIG_PATH
+    )]
+    pub config: PathBuf,
+    /// Overwrite the config file if it already exists.
+    #[clap(short, long)]
+    force: bool,
+}
+
+/// Print the JSON Schema of the config file for editor completion.
+#[derive(Parser)]
+pub struct Schema {}
+
+pub async fn run(opts: ConfigCommand) -> Result<()> {
+    match opts.subcmd {
+        ConfigSubCommand::Check(opts) => check(opts),
+        ConfigSubCommand::Init(opts) => init(opts),
+        ConfigSubCommand::Schema(_) => {
+            println!("{}", serde_json::to_string_pretty(&config_schema())?);
+            Ok(())
+        }
+    }
+}
+
+fn check(opts: Check) -> Result<()> {
+    let path = resolve_home_path(opts.config)?;
+    let content = fs::read_to_string(&path).with_context(|| {
+        format!(
+            "Could not read {}, create it with `spotifatius config init`",
+            path.display()
+        )
+    })?;
+    let path = path.display();
+
+    let value = match toml::from_str::<toml::Value>(&content) {
+        Ok(value) => value,
+        Err(err) => {
+            println!("error: {path}: {err}");
+            exit(1);
+        }
+    };
+    let mut has_errors = false;
+    for key in unknown_keys(&value) {
+        has_errors = true;
+        match key_line(&content, &key) {
+            Some(line) => println!("error: {path}:{line}: unknown key `{key}`"),
+            None => println!("error: {path}: unknown key `{key}`"),
+        }
+    }
+    let config = match toml::from_str::<Config>(&content) {
+        Ok(config) => config,
+        Err(err) => {
+            println!("error: {path}: {err}");
+            exit(1);
+        }
+    };
+
+    for result in [config.toast.duration(), config.hooks.timeout()] {
+        if let Err(err) = result {
+            has_errors = true;
+            println!("error: {path}: {err:#}");
+        }
+    }
+
+    let notifications = &config.notifications;
+    for (key, template) in [
+        ("summary", &notifications.summary),
+        ("body", &notifications.body),
+        ("liked_summary", &notifications.liked_summary),
+        ("liked_body", &notifications.liked_body),
+    ] {
+        for placeholder in placeholders(template) {
+            if !NOTIFICATION_PLACEHOLDERS.contains(&placeholder) {
+                has_errors = true;
+                println!("error: {path}: unknown placeholder `{{{placeholder}}}` in `notifications.{key}`");
+            }
+        }
+    }
+
+    let mut profiles: Vec<_> = config.profiles.keys().cloned().collect();
+    profiles.sort();
+    has_errors |= !check_output_options(
+        &path,
+        "",
+        Some(&config.format),
+        &config.formats,
+        &config.polybar.colors,
+    );
+    for name in &profiles {
+        let profile = &config.profiles[name];
+        has_errors |= !check_output_options(
+            &path,
+            &format!("profiles.{name}."),
+            profile.format.as_deref(),
+            &profile.formats,
+            &profile.polybar.colors,
+        );
+    }
+
+    println!("Rendered with sample tracks:");
+    render_samples(config.clone());
+    for name in profiles {
+        println!("Rendered with sample tracks for profile `{name}`:");
+        render_samples(config.clone().with_profile(Some(&name))?);
+    }
+
+    if has_errors {
+        exit(1);
+    }
+    println!("{path} is valid.");
+    Ok(())
+}
+
+/// Print the errors in the formats and colors, returns whether they're valid.
+fn check_output_options(
+    path: &impl Display,
+    prefix: &str,
+    format: Option<&str>,
+    formats: &FormatsConfig,
+    colors: &HashMap<String, String>,
+) -> bool {
+    let mut is_valid = true;
+    // The formats without a track don't have any placeholders.
+    let checks: [(&str, Option<&str>, &[&str]); 7] = [
+        ("format", format, &PLACEHOLDERS),
+        ("formats.playing", formats.playing.as_deref(), &PLACEHOLDERS),
+        ("formats.paused", formats.paused.as_deref(), &PLACEHOLDERS),
+        ("formats.ad", formats.ad.as_deref(), &PLACEHOLDERS),
+        ("formats.stopped", formats.stopped.as_deref(), &[]),
+        ("formats.added", formats.added.as_deref(), &[]),
+        ("formats.removed", formats.removed.as_deref(), &[]),
+    ];
+    for (key, format, allowed) in checks {
+        for placeholder in placeholders(format.unwrap_or_default()) {
+            if !allowed.contains(&placeholder) {
+                is_valid = false;
+                println!("error: {path}: unknown placeholder `{{{placeholder}}}` in `{prefix}{key}`");
+            }
+        }
+    }
+    for class in colors.keys() {
+        if !CLASSES.contains(&class.as_str()) {
+            println!("warning: {path}: `{prefix}polybar.colors.{class}` is not a class and is never used");
+        }
+    }
+    is_valid
+}
+
+fn render_samples(config: Config) {
+    let formatter = OutputFormatter {
+        output_type: OutputType::Waybar,
+        config,
+    };
+    let track = Track {
+        id: Some("4PTG3Z6ehGkBFwjybzWkR8".to_string()),
+        artist: Some("Rick Astley".to_string()),
+        title: Some("Never Gonna Give You Up".to_string()),
+        album: Some("Whenever You Need Somebody".to_string()),
+        is_ad: false,
+        art_url: None,
+        art_path: None,
+    };
+    let ad = Track {
+        id: Some("6KQ2bAbk0Ml6TBKrHmXhtq".to_string()),
+        artist: Some("Spotify".to_string()),
+        title: Some("Advertisement".to_string()),
+        album: None,
+        is_ad: true,
+        art_url: None,
+        art_path: None,
+    };
+    for (name, track, status, is_liked) in [
+        ("playing", Some(&track), TrackStatus::Playing, false),
+        ("playing liked", Some(&track), TrackStatus::Playing, true),
+        ("paused", Some(&track), TrackStatus::Paused, false),
+        ("ad", Some(&ad), TrackStatus::Playing, false),
+        ("added", Some(&track), TrackStatus::Added, true),
+        ("removed", Some(&track), TrackStatus::Removed, false),
+        ("stopped", None, TrackStatus::Stopped, false),
+    ] {
+        let response = MonitorResponse {
+            track: track.cloned(),
+            status: status.into(),
+            is_liked: Some(is_liked),
+            // Only used by the added and removed samples.
+            track_status: TrackStatus::Playing.into(),
+            ..Default::default()
+        };
+        let output =
+            formatter.format_output(response, status, &formatter.config.format);
+        if output.text.is_empty() {
+            println!("  {name}: (hidden)");
+        } else {
+            println!("  {name}: {}", output.text);
+        }
+    }
+}
+
+fn init(opts: Init) -> Result<()> {
+    let path = resolve_home_path(opts.config)?;
+    if path.exists() && !opts.force {
+        bail!(
+            "{} already exists, pass --force to overwrite it",
+            path.display()
+        );
+    }
+    if let Some(folder) = path.parent() {
+        create_dir_all(folder).with_context(|| {
+            format!("Could not create folder {}", folder.display())
+        })?;
+    }
+    fs::write(&path, DEFAULT_CONFIG)
+        .with_context(|| format!("Could not write {}", path.display()))?;
+    println!("Wrote the default config to {}", path.display());
+    Ok(())
+}
+
+/// Get the names of the `{placeholder}`s in the format.
+fn placeholders(format: &str) -> Vec<&str> {
+    format
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+        .collect()
+}
+
+/// Find the line number of the dotted key, e.g. `text_template.playing`.
+fn key_line(content: &str, key_path: &str) -> Option<usize> {
+    let (table, key) = match key_path.rsplit_once('.') {
+        Some((table, key)) => (Some(table), key),
+        None => (None, key_path),
+    };
+    // Top-level keys come before the first table.
+    let mut in_table = table.is_none();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix('[') {
+            let header = header.trim_matches(|c| c == '[' || c == ']').trim();
+            if header == key_path || header.starts_with(&format!("{key_path}."))
+            {
+                return Some(index + 1);
+            }
+            in_table = Some(header) == table;
+        } else if in_table {
+            if let Some(rest) = line.strip_prefix(key) {
+                let rest = rest.trim_start();
+                if rest.starts_with('=') || rest.starts_with('.') {
+                    return Some(index + 1);
+                }
+            }
+        }
+    }
+    None
+}
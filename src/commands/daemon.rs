@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+use tokio::{
+    select,
+    signal::unix::{signal, SignalKind},
+    sync::broadcast,
+};
+use tracing::info;
+
+use crate::{
+    server::{grpc::api::MonitorResponse, service::Service},
+    shared::config::{get_config, DEFAULT_CONFIG_PATH},
+};
+
+/// Run the server without printing the status.
+///
+/// `monitor` instances will mirror this server instead of starting one. It
+/// supports systemd socket activation and `Type=notify` services.
+#[derive(Parser)]
+pub struct Daemon {
+    /// Config file path.
+    #[clap(
+        short,
+        long,
+        parse(from_os_str),
+        default_value = DEFAULT_CONFIG_PATH
+    )]
+    pub config: PathBuf,
+}
+
+pub async fn run(opts: Daemon) -> Result<()> {
+    let config = get_config(opts.config)?;
+    let (monitor_tx, _) = broadcast::channel::<MonitorResponse>(1);
+    let mut service = Service::new(monitor_tx, &config)?;
+
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let mut sigint = signal(SignalKind::interrupt())?;
+    // Signals stop the server the same way as `spotifatius stop`.
+    let shutdown = async move {
+        select! {
+            _ = sigterm.recv() => info!("Received SIGTERM, stopping..."),
+            _ = sigint.recv() => info!("Received SIGINT, stopping..."),
+        }
+    };
+
+    service.daemon(shutdown).await
+}
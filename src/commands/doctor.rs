@@ -0,0 +1,163 @@
+use std::{io, path::PathBuf, process::exit};
+
+use anyhow::Result;
+use clap::Parser;
+use tokio::net::TcpListener;
+
+use crate::{
+    client::service::{ProtocolMismatch, Service},
+    server::{
+        dbus::{DBusClient, SPOTIFY_DEST},
+        spotify_client::{SpotifyClient, DEFAULT_REDIRECT_URI},
+    },
+    shared::{
+        config::{get_config, resolve_home_path, Config, DEFAULT_CONFIG_PATH},
+        consts::ADDRESS,
+    },
+};
+
+/// Diagnose common setup problems.
+///
+/// Checks the config, Spotify credentials, redirect URI, token cache, whether
+/// Spotify is running and the server address.
+#[derive(Parser)]
+pub struct Doctor {
+    /// Config file path.
+    #[clap(
+        short,
+        long,
+        parse(from_os_str),
+        default_value = DEFAULT_CONFIG_PATH
+    )]
+    pub config: PathBuf,
+}
+
+/// Prints the result of each check and keeps track of failures.
+#[derive(Default)]
+struct Report {
+    failed: bool,
+}
+
+impl Report {
+    fn pass(&self, message: impl AsRef<str>) {
+        println!("[PASS] {}", message.as_ref());
+    }
+
+    fn warn(&self, message: impl AsRef<str>, hint: impl AsRef<str>) {
+        println!("[WARN] {}\n       {}", message.as_ref(), hint.as_ref());
+    }
+
+    fn fail(&mut self, message: impl AsRef<str>, hint: impl AsRef<str>) {
+        self.failed = true;
+        println!("[FAIL] {}\n       {}", message.as_ref(), hint.as_ref());
+    }
+}
+
+pub async fn run(opts: Doctor) -> Result<()> {
+    let mut report = Report::default();
+
+    let config_path = resolve_home_path(opts.config.clone())?;
+    let config = match get_config(opts.config) {
+        Ok(config) => {
+            if config_path.exists() {
+                report
+                    .pass(format!("Config {} is valid", config_path.display()));
+            } else {
+                report.pass(format!(
+                    "Config {} does not exist, using the default config",
+                    config_path.display()
+                ));
+            }
+            config
+        }
+        Err(err) => {
+            report.fail(
+                format!("Config {} is invalid: {err:#}", config_path.display()),
+                "Fix the config file, see the README for the available options.",
+            );
+            toml::from_str::<Config>("")?
+        }
+    };
+
+    match SpotifyClient::new_read_only(&config.spotify) {
+        Ok(spotify) => {
+            report.pass("Spotify client ID found");
+
+            let redirect_uri = spotify.redirect_uri();
+            if redirect_uri.starts_with("http://localhost") {
+                report.fail(
+                    format!("Redirect URI {redirect_uri} is no longer allowed by Spotify"),
+                    format!("Use {DEFAULT_REDIRECT_URI} instead in your Spotify app and config. This was changed in 0.3.0."),
+                );
+            } else if redirect_uri != DEFAULT_REDIRECT_URI {
+                report.warn(
+                    format!("Redirect URI {redirect_uri} is not the default"),
+                    "Make sure the same redirect URI is allowed in your Spotify app.",
+                );
+            } else {
+                report.pass(format!("Redirect URI is {redirect_uri}"));
+            }
+
+            let cache_path = spotify.cache_path().display().to_string();
+            match spotify.load_token().await {
+                Ok(()) => report.pass(format!("Token cache {cache_path} found")),
+                Err(_) => report.fail(
+                    format!("No token cache at {cache_path}"),
+                    "Run `spotifatius auth` to authorize spotifatius.",
+                ),
+            }
+        }
+        Err(err) => report.fail(
+            format!("Spotify credentials are missing: {err:#}"),
+            "Set RSPOTIFY_CLIENT_ID or `client_id` in the [spotify] config section.",
+        ),
+    }
+
+    match DBusClient::player_owner().await {
+        Ok(Some(owner)) => report.pass(format!(
+            "Spotify is running on D-Bus as {SPOTIFY_DEST} ({owner})"
+        )),
+        Ok(None) => report.warn(
+            format!(
+                "Spotify is not running, {SPOTIFY_DEST} has no owner on D-Bus"
+            ),
+            "Start Spotify, nothing will be shown until it's running.",
+        ),
+        Err(err) => report.fail(
+            format!("Could not connect to the D-Bus session bus: {err:#}"),
+            "Make sure DBUS_SESSION_BUS_ADDRESS is set in the environment.",
+        ),
+    }
+
+    // Only the version is asked as it's answered right away, unlike the
+    // server info which waits for Spotify.
+    match Service::version().await {
+        Ok(version) => report.pass(format!(
+            "Server {} is running on {ADDRESS} with PID {}",
+            version.version, version.pid
+        )),
+        Err(err) if err.is::<ProtocolMismatch>() => report.fail(
+            format!("Server on {ADDRESS} uses a different protocol version"),
+            format!("{err:#}"),
+        ),
+        Err(_) => match TcpListener::bind(ADDRESS).await {
+            Ok(_) => report
+                .pass(format!("No server is running, {ADDRESS} is available")),
+            Err(err) if err.kind() == io::ErrorKind::AddrInUse => report.fail(
+                format!(
+                    "{ADDRESS} is used by something other than spotifatius"
+                ),
+                "Stop the program using it, e.g. find it with `ss -ltnp`.",
+            ),
+            Err(err) => report.fail(
+                format!("Could not bind {ADDRESS}: {err}"),
+                "Make sure IPv6 localhost is available.",
+            ),
+        },
+    }
+
+    if report.failed {
+        exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use std::{path::PathBuf, process::exit};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::{
+    client::service::{server_unavailable, ProtocolMismatch, Service},
+    server::{dbus::DBusClient, spotify_client::SpotifyClient},
+    shared::{
+        config::{get_config, DEFAULT_CONFIG_PATH},
+        consts::IS_LIKED_ERROR_EXIT_CODE,
+        track_id::parse_track_id,
+    },
+};
+
+/// Check whether a song is in your liked songs.
+///
+/// Exits with code 0 if it is, 1 if it isn't and 3 if it couldn't be checked.
+/// The monitoring instance is asked if there is one running, otherwise the
+/// Spotify Web API is asked directly about the given or playing track.
+#[derive(Parser)]
+pub struct IsLiked {
+    /// Spotify track URI, URL or ID, defaults to the currently playing song.
+    track: Option<String>,
+    /// Print the result as JSON.
+    #[clap(long)]
+    json: bool,
+    /// Config file path.
+    #[clap(
+        short,
+        long,
+        parse(from_os_str),
+        default_value = DEFAULT_CONFIG_PATH
+    )]
+    pub config: PathBuf,
+}
+
+#[derive(Serialize)]
+struct IsLikedOutput {
+    track_id: String,
+    is_liked: bool,
+}
+
+pub async fn run(opts: IsLiked) -> Result<()> {
+    if let Err(err) = check(opts).await {
+        // The default exit code of errors would read as not liked.
+        eprintln!("Error: {err:?}");
+        exit(IS_LIKED_ERROR_EXIT_CODE);
+    }
+    Ok(())
+}
+
+async fn check(opts: IsLiked) -> Result<()> {
+    let track_id = opts.track.as_deref().map(parse_track_id).transpose()?;
+
+    let output = match Service::is_liked(track_id.clone()).await {
+        Ok(response) => IsLikedOutput {
+            track_id: response.track_id,
+            is_liked: response.is_liked,
+        },
+        Err(err) if server_unavailable(&err) => {
+            if err.is::<ProtocolMismatch>() {
+                warn!("{err:#}");
+            }
+            debug!("Asking Spotify directly: {err:#}");
+            let track_id = match track_id {
+                Some(track_id) => track_id,
+                None => playing_track_id().await?,
+            };
+            let config = get_config(opts.config)?;
+            let spotify = SpotifyClient::new(&config.spotify)?;
+            spotify.load_token().await?;
+            let is_liked = spotify.saved_tracks_contains(&track_id).await?;
+            IsLikedOutput { track_id, is_liked }
+        }
+        Err(err) => return Err(err),
+    };
+
+    if opts.json {
+        println!("{}", serde_json::to_string(&output)?);
+    } else {
+        println!(
+            "{}",
+            if output.is_liked {
+                "Liked"
+            } else {
+                "Not liked"
+            }
+        );
+    }
+    if !output.is_liked {
+        exit(1);
+    }
+    Ok(())
+}
+
+/// Get the ID of the track playing in Spotify from MPRIS.
+async fn playing_track_id() -> Result<String> {
+    let track_change = DBusClient::current_track_change().await?.context(
+        "Neither a monitoring instance nor Spotify is running, pass the track to check",
+    )?;
+    track_change
+        .track
+        .id
+        .filter(|_| !track_change.track.is_ad)
+        .context("No track is playing, pass the track to check")
+}
@@ -1,4 +1,15 @@
+pub mod auth;
+pub mod config;
+pub mod daemon;
+pub mod doctor;
+pub mod is_liked;
+pub mod like;
 pub mod monitor;
 pub mod opts;
 mod output;
+pub mod restart;
+pub mod server_info;
+pub mod status;
+pub mod stop;
 pub mod toggle_liked;
+pub mod unlike;
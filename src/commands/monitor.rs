@@ -1,7 +1,7 @@
-use std::path::PathBuf;
+use std::{future, path::PathBuf};
 
 use tokio::{select, sync::broadcast};
-use tracing::debug;
+use tracing::{debug, info, warn};
 
 use anyhow::{Context, Result};
 use clap::Parser;
@@ -10,7 +10,10 @@ use crate::{
     commands::output::OutputType,
     server::grpc::api::{MonitorResponse, TrackStatus},
     server::service::Service,
-    shared::config::{get_config, DEFAULT_CONFIG_PATH},
+    shared::{
+        config::{get_config, resolve_home_path, Config, DEFAULT_CONFIG_PATH},
+        config_watcher::ConfigWatcher,
+    },
 };
 
 use super::output::OutputFormatter;
@@ -33,22 +36,34 @@ pub struct Monitor {
     /// Output type.
     #[clap(arg_enum, short, long, default_value = "waybar")]
     output_type: OutputType,
+    /// Name of the `[profiles.<name>]` config section to use.
+    #[clap(short, long)]
+    profile: Option<String>,
 }
 
 pub async fn run(opts: Monitor) -> Result<()> {
-    let config = get_config(opts.config)?;
-    let output_format = config.clone().format;
-    let formatter = OutputFormatter {
+    let config = load_config(&opts)?;
+    let exit_on_server_stop = config.monitor.exit_on_server_stop;
+    let mut formatter = OutputFormatter {
         output_type: opts.output_type,
         config,
     };
+    let mut config_watcher =
+        match ConfigWatcher::new(&resolve_home_path(opts.config.clone())?) {
+            Ok(config_watcher) => Some(config_watcher),
+            Err(err) => {
+                warn!("Could not watch the config for changes: {err:#}");
+                None
+            }
+        };
 
     let (monitor_tx, mut monitor_rx) =
         broadcast::channel::<MonitorResponse>(100);
-    let mut service = Service::new(monitor_tx).await?;
+    let mut service = Service::new(monitor_tx, &formatter.config)?;
 
     let mut monitor_handle =
-        tokio::spawn(async move { service.monitor().await });
+        tokio::spawn(async move { service.monitor(exit_on_server_stop).await });
+    let mut last_response = None;
 
     loop {
         select! {
@@ -58,11 +73,52 @@ pub async fn run(opts: Monitor) -> Result<()> {
             }
             Ok(response) = monitor_rx.recv() => {
                 debug!("{:#?}", response);
-                let status = TrackStatus::from_i32(response.status).context(format!("invalid status value '{}' passed", response.status))?;
-                let output = formatter.format_output(response, status, &output_format);
-                formatter.print(output)?;
+                print_response(&formatter, response.clone())?;
+                last_response = Some(response);
+            }
+            result = config_changed(&mut config_watcher) => {
+                result?;
+                // Only the output is reloaded, the server keeps using the
+                // config it was started with for Spotify, toasts and hooks.
+                match load_config(&opts) {
+                    Ok(config) => {
+                        info!("Reloaded the config");
+                        formatter.config = config;
+                        if let Some(response) = last_response.clone() {
+                            print_response(&formatter, response)?;
+                        }
+                    }
+                    Err(err) => warn!("Could not reload the config, keeping the old one: {err:#}"),
+                }
             }
         }
     }
     Ok(())
 }
+
+fn load_config(opts: &Monitor) -> Result<Config> {
+    get_config(opts.config.clone())?.with_profile(opts.profile.as_deref())
+}
+
+fn print_response(
+    formatter: &OutputFormatter,
+    response: MonitorResponse,
+) -> Result<()> {
+    let status = TrackStatus::from_i32(response.status).context(format!(
+        "invalid status value '{}' passed",
+        response.status
+    ))?;
+    let output =
+        formatter.format_output(response, status, &formatter.config.format);
+    formatter.print(output)
+}
+
+/// Wait until the config changed, or forever if it's not being watched.
+async fn config_changed(
+    config_watcher: &mut Option<ConfigWatcher>,
+) -> Result<()> {
+    match config_watcher {
+        Some(config_watcher) => config_watcher.changed().await,
+        None => future::pending().await,
+    }
+}
@@ -1,6 +1,11 @@
 use clap::{Parser, Subcommand};
 
-use super::{monitor::Monitor, toggle_liked::ToggleLiked};
+use super::{
+    auth::Auth, config::ConfigCommand, daemon::Daemon, doctor::Doctor,
+    is_liked::IsLiked, like::Like, monitor::Monitor, restart::Restart,
+    server_info::ServerInfo, status::Status, stop::Stop,
+    toggle_liked::ToggleLiked, unlike::Unlike,
+};
 
 /// A simple Spotify CLI primarily made for monitoring what songs you're
 /// listening to and displaying that in your bar of choice like waybar or polybar.
@@ -13,6 +18,17 @@ pub struct Opts {
 
 #[derive(Subcommand)]
 pub enum SubCommand {
+    Auth(Auth),
+    Config(ConfigCommand),
+    Daemon(Daemon),
+    Doctor(Doctor),
+    IsLiked(IsLiked),
+    Like(Like),
     Monitor(Monitor),
+    Restart(Restart),
+    ServerInfo(ServerInfo),
+    Status(Status),
+    Stop(Stop),
     ToggleLiked(ToggleLiked),
+    Unlike(Unlike),
 }
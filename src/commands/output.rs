@@ -3,7 +3,7 @@ use clap::ArgEnum;
 use serde::Serialize;
 
 use crate::{
-    shared::config::Config,
+    shared::config::{Config, ToastMode},
     server::grpc::api::{MonitorResponse, TrackStatus},
 };
 #[derive(Serialize, Clone)]
@@ -13,6 +13,9 @@ pub struct Output {
     pub tooltip: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub class: Option<Vec<String>>,
+    /// Path of the cached album art for widgets that can show it, like eww.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub art_path: Option<String>,
 }
 
 #[derive(ArgEnum, Clone, Copy)]
@@ -28,8 +31,33 @@ pub struct OutputFormatter {
 
 impl OutputFormatter {
     pub fn format_output(&self, response: MonitorResponse, status: TrackStatus, output_format: &str) -> Output {
+        let output = match status {
+            TrackStatus::Added | TrackStatus::Removed => self.format_toast(response, status, output_format),
+            _ => self.format_full_output(response, status, output_format),
+        };
+        match self.config.width {
+            Some(width) => Output {
+                text: truncate(&output.text, width),
+                ..output
+            },
+            None => output,
+        }
+    }
+
+    fn format_full_output(&self, response: MonitorResponse, status: TrackStatus, output_format: &str) -> Output {
         let text_template = &self.config.text_template;
+        let formats = &self.config.formats;
         if let Some(track) = response.track {
+            let output_format = if track.is_ad {
+                formats.ad.as_deref()
+            } else {
+                match status {
+                    TrackStatus::Playing => formats.playing.as_deref(),
+                    TrackStatus::Paused => formats.paused.as_deref(),
+                    _ => None,
+                }
+            }
+            .unwrap_or(output_format);
             let mut class = vec![];
             let mut separator = "-";
             let is_liked = response.is_liked.unwrap_or_default();
@@ -38,6 +66,15 @@ impl OutputFormatter {
                 separator = "+";
             }
             class.push(status.into());
+            if track.is_ad {
+                class.push("ad".to_string());
+            }
+            if response.offline {
+                class.push("offline".to_string());
+            }
+            if response.pending {
+                class.push("pending".to_string());
+            }
             let text = match (track.artist, track.title) {
                 (Some(artist), Some(title)) => {
                     let status_icon = match status {
@@ -53,7 +90,8 @@ impl OutputFormatter {
                     output_format
                         .replace("{artist}", &artist)
                         .replace("{title}", &title)
-                        .replace("{album}", track.album.as_ref().unwrap())
+                        .replace("{album}", track.album.as_deref().unwrap_or_default())
+                        .replace("{art_path}", track.art_path.as_deref().unwrap_or_default())
                         .replace("{separator}", separator)
                         .replace("{status}", status_icon)
                         .replace("{liked}", liked_icon)
@@ -66,24 +104,51 @@ impl OutputFormatter {
                 text,
                 tooltip: track.album,
                 class: Some(class),
+                art_path: track.art_path,
             }
-        } else if status == TrackStatus::Added {
-            Output {
-                text: "Added to library!".to_string(),
-                tooltip: None,
-                class: Some(vec![status.into()]),
-            }
-        } else if status == TrackStatus::Removed {
+        } else {
+            let text = formats.stopped.clone().unwrap_or_default();
             Output {
-                text: "Removed from library!".to_string(),
+                // Keep hiding the output when there's no text.
+                class: (!text.is_empty()).then(|| vec![TrackStatus::Stopped.into()]),
+                text,
                 tooltip: None,
-                class: Some(vec![status.into()]),
+                art_path: None,
             }
+        }
+    }
+
+    /// Format the message shown after liking or unliking the current track.
+    fn format_toast(&self, response: MonitorResponse, status: TrackStatus, output_format: &str) -> Output {
+        let formats = &self.config.formats;
+        let messages = &self.config.messages;
+        let text = if status == TrackStatus::Added {
+            formats.added.clone().unwrap_or_else(|| messages.added.clone())
         } else {
-            Output {
-                text: "".to_string(),
-                tooltip: None,
-                class: None,
+            formats.removed.clone().unwrap_or_else(|| messages.removed.clone())
+        };
+        let track_status = TrackStatus::from_i32(response.track_status).unwrap_or(TrackStatus::Stopped);
+        match self.config.toast.mode {
+            ToastMode::None => self.format_full_output(response, track_status, output_format),
+            // Older servers don't send the track with the message.
+            ToastMode::Append if response.track.is_some() => {
+                let output = self.format_full_output(response, track_status, output_format);
+                let mut class = output.class.unwrap_or_default();
+                class.push(status.into());
+                Output {
+                    text: format!("{} {text}", output.text),
+                    class: Some(class),
+                    ..output
+                }
+            }
+            _ => {
+                let track = response.track.unwrap_or_default();
+                Output {
+                    text,
+                    tooltip: track.album,
+                    class: Some(toast_class(status, response.pending)),
+                    art_path: track.art_path,
+                }
             }
         }
     }
@@ -118,12 +183,35 @@ impl OutputFormatter {
     }
 }
 
+/// Truncate the text to at most `width` characters, ending in `…` if it was
+/// too long.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut truncated: String =
+        text.chars().take(width.saturating_sub(1)).collect();
+    if width > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+fn toast_class(status: TrackStatus, pending: bool) -> Vec<String> {
+    let mut class = vec![status.into()];
+    if pending {
+        class.push("pending".to_string());
+    }
+    class
+}
+
 impl Default for Output {
     fn default() -> Self {
         Output {
             text: "".to_string(),
             tooltip: None,
             class: None,
+            art_path: None,
         }
     }
 }
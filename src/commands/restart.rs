@@ -0,0 +1,92 @@
+use std::{
+    env,
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use tokio::time::{self, Instant};
+
+use crate::{
+    client::service::{ProtocolMismatch, Service},
+    server::grpc::api::VersionResponse,
+    shared::config::DEFAULT_CONFIG_PATH,
+};
+
+use super::stop::stop_server;
+
+/// How long to wait for a monitoring instance to take over as the server.
+const TAKEOVER_TIMEOUT: Duration = Duration::from_secs(3);
+const START_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Restart the running server, e.g. after upgrading spotifatius.
+///
+/// A monitoring instance mirroring the server takes over if there is one,
+/// otherwise `spotifatius daemon` is started in the background.
+#[derive(Parser)]
+pub struct Restart {
+    /// Config file path, used when starting `spotifatius daemon`.
+    #[clap(
+        short,
+        long,
+        parse(from_os_str),
+        default_value = DEFAULT_CONFIG_PATH
+    )]
+    pub config: PathBuf,
+}
+
+pub async fn run(opts: Restart) -> Result<()> {
+    let old_pid = stop_server().await?;
+    println!("Stopped the server (PID {old_pid}).");
+
+    let server = match wait_for_server(TAKEOVER_TIMEOUT).await? {
+        Some(server) => server,
+        None => {
+            start_daemon(&opts.config)?;
+            wait_for_server(START_TIMEOUT).await?.context(
+                "The server did not start, run `spotifatius daemon` to see why",
+            )?
+        }
+    };
+    println!(
+        "Started spotifatius {} as the server (PID {}).",
+        server.version, server.pid
+    );
+    Ok(())
+}
+
+/// Wait until a server with the same protocol version is reachable.
+async fn wait_for_server(timeout: Duration) -> Result<Option<VersionResponse>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match Service::version().await {
+            Ok(server) => return Ok(Some(server)),
+            Err(err) if err.is::<ProtocolMismatch>() => bail!(
+                "A monitoring instance running a different version took over as the server, restart your bar so it uses spotifatius {}",
+                env!("CARGO_PKG_VERSION")
+            ),
+            Err(_) if Instant::now() < deadline => {
+                time::sleep(Duration::from_millis(100)).await
+            }
+            Err(_) => return Ok(None),
+        }
+    }
+}
+
+fn start_daemon(config: &Path) -> Result<()> {
+    Command::new(env::current_exe()?)
+        .arg("daemon")
+        .arg("--config")
+        .arg(config)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        // Keep it running when the terminal is closed.
+        .process_group(0)
+        .spawn()
+        .context("Could not start `spotifatius daemon`")?;
+    Ok(())
+}
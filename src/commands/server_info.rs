@@ -0,0 +1,35 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::client::service::Service;
+
+/// Show information about the running server for debugging.
+/// Requires a monitoring instance to be running.
+#[derive(Parser)]
+pub struct ServerInfo {}
+
+pub async fn run(_opts: ServerInfo) -> Result<()> {
+    let info = Service::server_info().await?;
+    let none = "none".to_string();
+    println!("Version: {}", info.version);
+    println!("PID: {}", info.pid);
+    println!("Uptime: {}s", info.uptime_seconds);
+    println!(
+        "Player: {} (owner: {})",
+        info.player_bus_name,
+        info.player_owner
+            .unwrap_or_else(|| "not running".to_string())
+    );
+    println!("Connected mirrors: {}", info.mirrors);
+    println!("Cached tracks: {}", info.cached_tracks);
+    println!("Pending liked changes: {}", info.pending_changes);
+    println!(
+        "Token expires at: {}",
+        info.token_expires_at.as_ref().unwrap_or(&none)
+    );
+    println!(
+        "Last Web API error: {}",
+        info.last_api_error.as_ref().unwrap_or(&none)
+    );
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use tracing::{debug, warn};
+
+use crate::{
+    client::service::{server_unavailable, ProtocolMismatch, Service},
+    server::{
+        dbus::DBusClient,
+        grpc::api::{MonitorResponse, TrackStatus},
+    },
+    shared::config::{get_config, DEFAULT_CONFIG_PATH},
+};
+
+use super::output::{OutputFormatter, OutputType};
+
+/// Print the status of the currently playing song on Spotify once.
+///
+/// This is useful for bars that run a command on an interval instead of
+/// monitoring its output. The monitoring instance is asked if there is one
+/// running, otherwise Spotify is asked directly without the liked state.
+#[derive(Parser)]
+pub struct Status {
+    /// Config file path.
+    #[clap(
+        short,
+        long,
+        parse(from_os_str),
+        default_value = DEFAULT_CONFIG_PATH
+    )]
+    pub config: PathBuf,
+    /// Output type.
+    #[clap(arg_enum, short, long, default_value = "waybar")]
+    output_type: OutputType,
+    /// Name of the `[profiles.<name>]` config section to use.
+    #[clap(short, long)]
+    profile: Option<String>,
+}
+
+pub async fn run(opts: Status) -> Result<()> {
+    let config =
+        get_config(opts.config)?.with_profile(opts.profile.as_deref())?;
+    let output_format = config.clone().format;
+    let formatter = OutputFormatter {
+        output_type: opts.output_type,
+        config,
+    };
+
+    let response = match Service::get_current().await {
+        Ok(response) => response,
+        Err(err) if server_unavailable(&err) => {
+            if err.is::<ProtocolMismatch>() {
+                warn!("{err:#}");
+            }
+            debug!("Asking Spotify directly: {err:#}");
+            match DBusClient::current_track_change().await? {
+                Some(track_change) if track_change.track.id.is_some() => {
+                    MonitorResponse {
+                        track: Some(track_change.track),
+                        status: track_change.status.into(),
+                        track_status: track_change.status.into(),
+                        ..Default::default()
+                    }
+                }
+                _ => MonitorResponse {
+                    status: TrackStatus::Stopped.into(),
+                    track_status: TrackStatus::Stopped.into(),
+                    ..Default::default()
+                },
+            }
+        }
+        Err(err) => return Err(err),
+    };
+
+    debug!("{:#?}", response);
+    let status = TrackStatus::from_i32(response.status).context(format!(
+        "invalid status value '{}' passed",
+        response.status
+    ))?;
+    let output = formatter.format_output(response, status, &output_format);
+    formatter.print(output)
+}
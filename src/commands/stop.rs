@@ -0,0 +1,40 @@
+use std::{path::Path, time::Duration};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use tokio::time::{self, Instant};
+
+use crate::client::service::Service;
+
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Stop the running server.
+///
+/// Monitoring instances mirroring it take over as the server, unless
+/// `exit_on_server_stop` is set in their config.
+#[derive(Parser)]
+pub struct Stop {}
+
+pub async fn run(_opts: Stop) -> Result<()> {
+    let pid = stop_server().await?;
+    println!("Stopped the server (PID {pid}).");
+    Ok(())
+}
+
+/// Ask the server to stop and wait for its process to exit, returns its PID.
+pub async fn stop_server() -> Result<u32> {
+    let pid = Service::shutdown().await?;
+    // Checking the process instead of connecting, as that would start the
+    // server again when using systemd socket activation.
+    let process_path = format!("/proc/{pid}");
+    let deadline = Instant::now() + STOP_TIMEOUT;
+    while Path::new(&process_path).exists() {
+        if Instant::now() > deadline {
+            bail!(
+                "The server (PID {pid}) did not stop within {STOP_TIMEOUT:?}"
+            );
+        }
+        time::sleep(Duration::from_millis(100)).await;
+    }
+    Ok(pid)
+}
@@ -1,7 +1,10 @@
+use std::path::PathBuf;
+
 use anyhow::Result;
 use clap::Parser;
 
 use crate::client::service::Service;
+use crate::shared::config::{get_config, DEFAULT_CONFIG_PATH};
 
 /// Add/remove the currently playing song to your liked songs.
 /// Requires a monitoring instance to be running.
@@ -9,8 +12,18 @@ use crate::client::service::Service;
 /// This will also make sure the liked message is shown on the monitoring
 /// instances.
 #[derive(Parser)]
-pub struct ToggleLiked {}
+pub struct ToggleLiked {
+    /// Config file path, used for the messages.
+    #[clap(
+        short,
+        long,
+        parse(from_os_str),
+        default_value = DEFAULT_CONFIG_PATH
+    )]
+    pub config: PathBuf,
+}
 
-pub async fn run(_opts: ToggleLiked) -> Result<()> {
-    Service::toggle_liked().await
+pub async fn run(opts: ToggleLiked) -> Result<()> {
+    let config = get_config(opts.config)?;
+    Service::toggle_liked(&config.messages).await
 }
@@ -0,0 +1,38 @@
+use std::{path::PathBuf, process::exit};
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::client::service::Service;
+use crate::shared::{
+    config::{get_config, DEFAULT_CONFIG_PATH},
+    consts::UNCHANGED_EXIT_CODE,
+    track_id::parse_track_id,
+};
+
+/// Remove a song from your liked songs if it isn't already.
+/// Requires a monitoring instance to be running.
+///
+/// Exits with code 2 if the song was not liked.
+#[derive(Parser)]
+pub struct Unlike {
+    /// Spotify track URI, URL or ID, defaults to the currently playing song.
+    track: Option<String>,
+    /// Config file path, used for the messages.
+    #[clap(
+        short,
+        long,
+        parse(from_os_str),
+        default_value = DEFAULT_CONFIG_PATH
+    )]
+    pub config: PathBuf,
+}
+
+pub async fn run(opts: Unlike) -> Result<()> {
+    let track_id = opts.track.as_deref().map(parse_track_id).transpose()?;
+    let config = get_config(opts.config)?;
+    if !Service::set_liked(track_id, false, &config.messages).await? {
+        exit(UNCHANGED_EXIT_CODE);
+    }
+    Ok(())
+}
@@ -7,7 +7,10 @@ use crate::commands::opts::{Opts, SubCommand};
 use anyhow::Result;
 
 use clap::Parser;
-use commands::{monitor, toggle_liked};
+use commands::{
+    auth, config, daemon, doctor, is_liked, like, monitor, restart,
+    server_info, status, stop, toggle_liked, unlike,
+};
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::EnvFilter;
 
@@ -24,7 +27,18 @@ async fn main() -> Result<()> {
     let root_opts = Opts::parse();
 
     match root_opts.subcmd {
+        SubCommand::Auth(opts) => auth::run(opts).await,
+        SubCommand::Config(opts) => config::run(opts).await,
+        SubCommand::Daemon(opts) => daemon::run(opts).await,
+        SubCommand::Doctor(opts) => doctor::run(opts).await,
+        SubCommand::IsLiked(opts) => is_liked::run(opts).await,
+        SubCommand::Like(opts) => like::run(opts).await,
         SubCommand::Monitor(opts) => monitor::run(opts).await,
+        SubCommand::Restart(opts) => restart::run(opts).await,
+        SubCommand::ServerInfo(opts) => server_info::run(opts).await,
+        SubCommand::Status(opts) => status::run(opts).await,
+        SubCommand::Stop(opts) => stop::run(opts).await,
         SubCommand::ToggleLiked(opts) => toggle_liked::run(opts).await,
+        SubCommand::Unlike(opts) => unlike::run(opts).await,
     }
 }
@@ -0,0 +1,157 @@
+use std::{
+    fs::{self, create_dir_all, File},
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{bail, Context, Result};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::server::grpc::api::Track;
+use crate::shared::config::{cache_folder, AlbumArtConfig};
+
+const ALBUM_ART_FOLDER: &str = "album_art";
+/// Larger downloads are refused, Spotify's images are far smaller.
+const MAX_IMAGE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Downloads album art to the cache folder, keyed by the image ID.
+///
+/// When the images exceed the size limit, the least recently used ones are
+/// removed. The modification time of an image is used as its last use.
+pub struct AlbumArt {
+    folder: PathBuf,
+    max_size: u64,
+    client: reqwest::Client,
+    /// Held while downloading so an image isn't downloaded twice at once.
+    download_lock: Mutex<()>,
+}
+
+impl AlbumArt {
+    pub fn new(config: &AlbumArtConfig) -> Result<Self> {
+        let folder = cache_folder()?.join(ALBUM_ART_FOLDER);
+        create_dir_all(&folder).with_context(|| {
+            format!("Could not create folder {}", folder.display())
+        })?;
+        Ok(AlbumArt {
+            folder,
+            max_size: config.max_size_mb * 1024 * 1024,
+            client: reqwest::Client::new(),
+            download_lock: Mutex::new(()),
+        })
+    }
+
+    fn path(&self, url: &str) -> Result<PathBuf> {
+        // The URLs end in the image ID, e.g.
+        // "https://i.scdn.co/image/ab67616d0000b273{IMAGE_ID}".
+        let id = url
+            .rsplit('/')
+            .next()
+            .filter(|id| {
+                !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric())
+            })
+            .with_context(|| format!("Unsupported album art URL: {url}"))?;
+        Ok(self.folder.join(format!("{id}.jpg")))
+    }
+
+    /// Get the path of the image if it's cached, marking it as used.
+    pub fn cached_path(&self, url: &str) -> Option<PathBuf> {
+        let path = self.path(url).ok()?;
+        touch(&path).ok()?;
+        Some(path)
+    }
+
+    /// Get the path of the image, downloading it if it's not cached.
+    pub async fn get(&self, url: &str) -> Result<PathBuf> {
+        if let Some(path) = self.cached_path(url) {
+            return Ok(path);
+        }
+        let _lock = self.download_lock.lock().await;
+        // It could have been downloaded while waiting for the lock.
+        if let Some(path) = self.cached_path(url) {
+            return Ok(path);
+        }
+
+        let path = self.path(url)?;
+        debug!("Downloading album art {url} to {}", path.display());
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Could not download {url}"))?;
+        if response.content_length().unwrap_or_default() > MAX_IMAGE_SIZE {
+            bail!("{url} is larger than {MAX_IMAGE_SIZE} bytes");
+        }
+        let image = response
+            .bytes()
+            .await
+            .with_context(|| format!("Could not download {url}"))?;
+        if image.len() as u64 > MAX_IMAGE_SIZE {
+            bail!("{url} is larger than {MAX_IMAGE_SIZE} bytes");
+        }
+        // Readers never see a partially written image this way.
+        let partial_path = path.with_extension("part");
+        fs::write(&partial_path, &image)
+            .and_then(|_| fs::rename(&partial_path, &path))
+            .with_context(|| format!("Could not write {}", path.display()))?;
+
+        self.evict(&path).with_context(|| {
+            format!("Could not clean up {}", self.folder.display())
+        })?;
+        Ok(path)
+    }
+
+    /// Set the path of the track's album art if it isn't yet, downloading it
+    /// if needed. Failing to do so is logged as the album art is optional.
+    pub async fn resolve(&self, track: &mut Track) {
+        let Some(url) =
+            track.art_url.as_ref().filter(|_| track.art_path.is_none())
+        else {
+            return;
+        };
+        match self.get(url).await {
+            Ok(path) => track.art_path = Some(path.display().to_string()),
+            Err(err) => warn!("Could not get the album art: {err:#}"),
+        }
+    }
+
+    /// Remove the least recently used images, except the one to keep, until
+    /// the images fit in the size limit.
+    fn evict(&self, keep: &Path) -> io::Result<()> {
+        let mut images = vec![];
+        let mut size = 0;
+        for entry in fs::read_dir(&self.folder)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            size += metadata.len();
+            images.push((metadata.modified()?, metadata.len(), entry.path()));
+        }
+        images.sort();
+        for (_, len, path) in images {
+            if size <= self.max_size {
+                break;
+            }
+            if path == keep {
+                continue;
+            }
+            debug!("Removing album art {}", path.display());
+            fs::remove_file(&path)?;
+            size -= len;
+        }
+        Ok(())
+    }
+}
+
+/// Set the modification time to now, fails if the file doesn't exist.
+fn touch(path: &Path) -> io::Result<()> {
+    File::options()
+        .append(true)
+        .open(path)?
+        .set_modified(SystemTime::now())
+}
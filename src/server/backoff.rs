@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use rspotify::{http::HttpError, ClientError};
+
+const MIN_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(300);
+
+/// Exponential backoff used when the Spotify Web API or the server can't be
+/// reached.
+pub struct Backoff {
+    delay: Duration,
+    max_delay: Duration,
+}
+
+impl Backoff {
+    pub fn new() -> Self {
+        Self::with_max_delay(MAX_DELAY)
+    }
+
+    pub fn with_max_delay(max_delay: Duration) -> Self {
+        Self {
+            delay: MIN_DELAY,
+            max_delay,
+        }
+    }
+
+    /// Get the delay to wait before the next attempt and double it for the
+    /// attempt after that.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.delay;
+        self.delay = (self.delay * 2).min(self.max_delay);
+        delay
+    }
+
+    /// Same as `next_delay`, but a `Retry-After` sent by Spotify takes
+    /// precedence.
+    pub fn next_delay_after(&mut self, error: &anyhow::Error) -> Duration {
+        retry_after(error).unwrap_or_else(|| self.next_delay())
+    }
+
+    /// Reset the delay after a successful attempt.
+    pub fn reset(&mut self) {
+        self.delay = MIN_DELAY;
+    }
+}
+
+/// Whether the request could succeed when retried: Spotify couldn't be
+/// reached, timed out, failed with a 5xx or rate limited it. Other errors,
+/// such as a revoked token or an unknown track, fail the same way again.
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<ClientError>() {
+        Some(ClientError::Http(http_error)) => match http_error.as_ref() {
+            HttpError::Client(_) => true,
+            HttpError::StatusCode(response) => {
+                let status = response.status();
+                status.is_server_error() || status.as_u16() == 429
+            }
+        },
+        Some(ClientError::Io(_)) => true,
+        _ => false,
+    }
+}
+
+/// Get the `Retry-After` duration if the error is a 429 Too Many Requests.
+fn retry_after(error: &anyhow::Error) -> Option<Duration> {
+    match error.downcast_ref::<ClientError>()? {
+        ClientError::Http(http_error) => match http_error.as_ref() {
+            HttpError::StatusCode(response)
+                if response.status().as_u16() == 429 =>
+            {
+                response
+                    .headers()
+                    .get("retry-after")
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .map(Duration::from_secs)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -22,20 +22,23 @@ use super::grpc::api::ChangeEvent;
 const FREEDESKTOP_DEST: &str = "org.freedesktop.DBus";
 const FREEDESKTOP_PATH: &str = "/org/freedesktop/DBus";
 
-const SPOTIFY_DEST: &str = "org.mpris.MediaPlayer2.spotify";
-const SPOTIFY_PATH: &str = "/org/mpris/MediaPlayer2";
+pub const SPOTIFY_DEST: &str = "org.mpris.MediaPlayer2.spotify";
+pub const SPOTIFY_PATH: &str = "/org/mpris/MediaPlayer2";
 
 pub struct DBusClient {
+    connection: Connection,
     events_tx: mpsc::Sender<ChangeEvent>,
     update_requests_tx: broadcast::Sender<()>,
 }
 
 impl DBusClient {
     pub fn new(
+        connection: Connection,
         events_tx: mpsc::Sender<ChangeEvent>,
         update_requests_tx: broadcast::Sender<()>,
     ) -> Self {
         DBusClient {
+            connection,
             events_tx,
             update_requests_tx,
         }
@@ -49,10 +52,13 @@ impl DBusClient {
 
         self.update_requests_tx.send(())?;
 
-        let mut window_handle =
-            tokio::spawn(DBusClient::listen_spotify_window(change_tx.clone()));
+        let mut window_handle = tokio::spawn(DBusClient::listen_spotify_window(
+            self.connection.clone(),
+            change_tx.clone(),
+        ));
 
         let mut change_handle = tokio::spawn(DBusClient::listen_song_changes(
+            self.connection.clone(),
             change_tx.clone(),
             BroadcastStream::new(update_requests_rx),
         ));
@@ -88,10 +94,10 @@ impl DBusClient {
     /// an explicit update. That can be useful for example when Spotify opens,
     /// but hasn't sent a song update yet. You would then request one explicitly.
     async fn listen_song_changes(
+        connection: Connection,
         events: mpsc::Sender<ChangeEvent>,
         update_requests_rx: BroadcastStream<()>,
     ) -> Result<()> {
-        let connection = Connection::session().await?;
         let props = PropertiesProxy::builder(&connection)
             .destination(SPOTIFY_DEST)?
             .path(SPOTIFY_PATH)?
@@ -175,77 +181,8 @@ impl DBusClient {
             debug!("metadata_value: {metadata_value:#?}");
             debug!("is_update_request: {is_update_request:#?}");
 
-            let status = playback_value
-                .map(|value: OwnedValue| -> Value { value.into() })
-                .and_then(|value| value.clone().downcast::<String>())
-                .and_then(|value| TrackStatus::from_str(value.as_str()).ok())
-                .unwrap_or(TrackStatus::Stopped);
-
-            let metadata = metadata_value
-                .map(|value: OwnedValue| -> Value { value.into() })
-                .and_then(|value| value.clone().downcast::<Dict>())
-                .and_then(|value| -> Option<HashMap<String, Value>> {
-                    value.try_into().ok()
-                });
-
-            let artist = metadata
-                .as_ref()
-                .and_then(|value| {
-                    value
-                        .get("xesam:artist")
-                        .and_then(|value| value.clone().downcast::<Array>())
-                })
-                .and_then(|arr| {
-                    arr.iter()
-                        .map(|value| value.try_into().ok())
-                        .collect::<Option<Vec<String>>>()
-                        .and_then(|items| items.into_iter().next())
-                })
-                .filter(|value| !value.is_empty());
-
-            let title = metadata
-                .as_ref()
-                .and_then(|value| {
-                    value
-                        .get("xesam:title")
-                        .and_then(|value| value.clone().downcast::<String>())
-                })
-                .filter(|value| !value.is_empty());
-
-            let album = metadata
-                .as_ref()
-                .and_then(|value| {
-                    value
-                        .get("xesam:album")
-                        .and_then(|value| value.clone().downcast::<String>())
-                })
-                .filter(|value| !value.is_empty());
-
-            let id = metadata
-                .as_ref()
-                .and_then(|value| {
-                    value
-                        .get("mpris:trackid")
-                        .and_then(|value| value.clone().downcast::<String>())
-                })
-                .filter(|value| !value.is_empty())
-                .and_then(|value| {
-                    // There are 2 track ID formats. One of them being:
-                    // "/com/spotify/track/{TRACK_ID}", the other being
-                    // similar but with ":" instead of "/" and also
-                    // ending with "{TRACK_ID}" so we pick the last.
-                    value.split(&['/', ':']).last().map(|raw| raw.to_string())
-                });
-
-            let song_change = TrackChange {
-                status,
-                track: Track {
-                    artist,
-                    title,
-                    album,
-                    id,
-                },
-            };
+            let song_change =
+                parse_track_change(playback_value, metadata_value);
             if let Some(last) = last_song_change.clone() {
                 if !is_update_request && last == song_change {
                     debug!("Skip sending track change as it was the same and not an update request");
@@ -260,12 +197,57 @@ impl DBusClient {
         Ok(())
     }
 
+    /// Get what is currently playing in Spotify without listening for changes.
+    ///
+    /// Returns `None` if Spotify is not running.
+    pub async fn current_track_change() -> Result<Option<TrackChange>> {
+        let connection = Connection::session().await?;
+        let dbus = DBusProxy::builder(&connection)
+            .destination(FREEDESKTOP_DEST)?
+            .path(FREEDESKTOP_PATH)?
+            .build()
+            .await?;
+        if !dbus.name_has_owner(SPOTIFY_DEST.try_into()?).await? {
+            return Ok(None);
+        }
+
+        let props = PropertiesProxy::builder(&connection)
+            .destination(SPOTIFY_DEST)?
+            .path(SPOTIFY_PATH)?
+            .build()
+            .await?;
+        let player_interface_name =
+            InterfaceName::try_from("org.mpris.MediaPlayer2.Player")?;
+        let playback_value = props
+            .get(player_interface_name.clone(), "PlaybackStatus")
+            .await
+            .ok();
+        let metadata_value =
+            props.get(player_interface_name, "Metadata").await.ok();
+
+        Ok(Some(parse_track_change(playback_value, metadata_value)))
+    }
+
+    /// Get the unique bus name currently owning the Spotify MPRIS bus name.
+    pub async fn player_owner() -> Result<Option<String>> {
+        let connection = Connection::session().await?;
+        let dbus = DBusProxy::builder(&connection)
+            .destination(FREEDESKTOP_DEST)?
+            .path(FREEDESKTOP_PATH)?
+            .build()
+            .await?;
+        Ok(dbus
+            .get_name_owner(SPOTIFY_DEST.try_into()?)
+            .await
+            .ok()
+            .map(|owner| owner.to_string()))
+    }
+
     /// Listen for when the Spotify window is opened or closed.
     async fn listen_spotify_window(
+        connection: Connection,
         events: mpsc::Sender<ChangeEvent>,
     ) -> Result<()> {
-        let connection = Connection::session().await?;
-
         let dbus = DBusProxy::builder(&connection)
             .destination(FREEDESKTOP_DEST)?
             .path(FREEDESKTOP_PATH)?
@@ -296,3 +278,106 @@ impl DBusClient {
         Ok(())
     }
 }
+
+/// Parse the MPRIS `PlaybackStatus` and `Metadata` properties.
+fn parse_track_change(
+    playback_value: Option<OwnedValue>,
+    metadata_value: Option<OwnedValue>,
+) -> TrackChange {
+    let status = playback_value
+        .map(|value: OwnedValue| -> Value { value.into() })
+        .and_then(|value| value.clone().downcast::<String>())
+        .and_then(|value| TrackStatus::from_str(value.as_str()).ok())
+        .unwrap_or(TrackStatus::Stopped);
+
+    let metadata = metadata_value
+        .map(|value: OwnedValue| -> Value { value.into() })
+        .and_then(|value| value.clone().downcast::<Dict>())
+        .and_then(|value| -> Option<HashMap<String, Value>> {
+            value.try_into().ok()
+        });
+
+    let artist = metadata
+        .as_ref()
+        .and_then(|value| {
+            value
+                .get("xesam:artist")
+                .and_then(|value| value.clone().downcast::<Array>())
+        })
+        .and_then(|arr| {
+            arr.iter()
+                .map(|value| value.try_into().ok())
+                .collect::<Option<Vec<String>>>()
+                .and_then(|items| items.into_iter().next())
+        })
+        .filter(|value| !value.is_empty());
+
+    let title = metadata
+        .as_ref()
+        .and_then(|value| {
+            value
+                .get("xesam:title")
+                .and_then(|value| value.clone().downcast::<String>())
+        })
+        .filter(|value| !value.is_empty());
+
+    let album = metadata
+        .as_ref()
+        .and_then(|value| {
+            value
+                .get("xesam:album")
+                .and_then(|value| value.clone().downcast::<String>())
+        })
+        .filter(|value| !value.is_empty());
+
+    let raw_id = metadata
+        .as_ref()
+        .and_then(|value| {
+            value
+                .get("mpris:trackid")
+                .and_then(|value| value.clone().downcast::<String>())
+        })
+        .filter(|value| !value.is_empty());
+    // Ads have IDs like "/com/spotify/ad/{AD_ID}".
+    let is_ad = raw_id.as_ref().map_or(false, |value| {
+        value.contains("/ad/") || value.contains(":ad:")
+    });
+    let id = raw_id.and_then(|value| {
+        // There are 2 track ID formats. One of them being:
+        // "/com/spotify/track/{TRACK_ID}", the other being
+        // similar but with ":" instead of "/" and also
+        // ending with "{TRACK_ID}" so we pick the last.
+        value.split(&['/', ':']).last().map(|raw| raw.to_string())
+    });
+
+    let art_url = metadata
+        .as_ref()
+        .and_then(|value| {
+            value
+                .get("mpris:artUrl")
+                .and_then(|value| value.clone().downcast::<String>())
+        })
+        .filter(|value| !value.is_empty())
+        // Older Spotify versions send URLs on a host that no longer serves
+        // the images.
+        .map(|value| {
+            value.replace(
+                "https://open.spotify.com/image/",
+                "https://i.scdn.co/image/",
+            )
+        });
+
+    TrackChange {
+        status,
+        track: Track {
+            artist,
+            title,
+            album,
+            id,
+            is_ad,
+            art_url,
+            // Set by the server once it's cached.
+            art_path: None,
+        },
+    }
+}
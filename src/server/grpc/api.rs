@@ -12,6 +12,10 @@ pub enum ChangeEvent {
     SpotifyClosed,
     TrackChange(TrackChange),
     TrackLiked(bool),
+    /// A liked change was queued to be sent to Spotify later.
+    PendingQueued,
+    /// The album art with the URL was downloaded to the path.
+    AlbumArtCached(String, String),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -1,4 +1,3 @@
 pub mod api;
 pub mod monitor_client;
 pub mod server;
-pub mod wake_watcher;
@@ -1,21 +1,48 @@
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use super::api::MonitorResponse;
 use anyhow::Result;
 use futures::Stream;
 
-use tokio::sync::broadcast::error::TryRecvError;
-use tokio::sync::broadcast::Receiver;
+use tokio::sync::watch::Receiver;
+use tokio_stream::wrappers::WatchStream;
 use tonic::Status;
 
 use std::task::{Context, Poll};
 
-use super::wake_watcher::WakeWatcher;
-
+/// The monitor stream of a single subscriber.
+///
+/// It's backed by the watch channel holding the latest response, so a slow
+/// subscriber skips to the newest response instead of lagging behind and is
+/// only woken when there is a new response.
 pub struct MonitorClient {
-    pub rx: Receiver<MonitorResponse>,
-    pub wake_watcher: Arc<WakeWatcher>,
+    inner: WatchStream<Option<MonitorResponse>>,
+    /// The number of connected subscribers, decremented when dropped.
+    connected: Arc<AtomicUsize>,
+    /// Whether the stopping response was sent, which ends the stream.
+    stopped: bool,
+}
+
+impl MonitorClient {
+    pub fn new(
+        current_rx: Receiver<Option<MonitorResponse>>,
+        connected: Arc<AtomicUsize>,
+    ) -> Self {
+        connected.fetch_add(1, Ordering::Relaxed);
+        Self {
+            inner: WatchStream::new(current_rx),
+            connected,
+            stopped: false,
+        }
+    }
+}
+
+impl Drop for MonitorClient {
+    fn drop(&mut self) {
+        self.connected.fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
 impl Stream for MonitorClient {
@@ -26,36 +53,21 @@ impl Stream for MonitorClient {
         ctx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
         let s = self.get_mut();
-        let response = s.rx.try_recv();
-
-        let poll = match response {
-            Err(err) => {
-                match err {
-                    TryRecvError::Closed => {
-                        Poll::Ready(Some(Err(Status::not_found(
-                            "The monitoring server closed.",
-                        ))))
-                    }
-                    TryRecvError::Empty => {
-                        match s.wake_watcher.add_waker(ctx.waker().to_owned()) {
-                            Ok(_) => Poll::Pending,
-                            Err(err) => {
-                                Poll::Ready(Some(Err(Status::internal(
-                                    err.to_string(),
-                                ))))
-                            }
-                        }
-                    }
-                    TryRecvError::Lagged(_) => {
-                        Poll::Ready(Some(Err(Status::internal(
-                            "The receiver lagged behind for some reason.",
-                        ))))
-                    }
+        if s.stopped {
+            return Poll::Ready(None);
+        }
+        loop {
+            match Pin::new(&mut s.inner).poll_next(ctx) {
+                Poll::Ready(Some(Some(response))) => {
+                    s.stopped = response.stopping;
+                    return Poll::Ready(Some(Ok(response)));
                 }
+                // There is no response yet right after the server started.
+                Poll::Ready(Some(None)) => continue,
+                // The monitoring server closed.
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
             }
-            Ok(resp) => Poll::Ready(Some(Ok(resp))),
-        };
-
-        poll
+        }
     }
 }
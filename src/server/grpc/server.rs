@@ -1,46 +1,73 @@
 use std::pin::Pin;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::server::dbus::{DBusClient, SPOTIFY_DEST};
 use crate::server::liked_tracker::LikedTracker;
+use crate::shared::consts::PROTOCOL_VERSION;
 
 use super::api::spotifatius_server::Spotifatius;
 use super::api::{
-    MonitorRequest, MonitorResponse, ToggleLikedRequest, ToggleLikedResponse,
+    GetCurrentRequest, IsLikedRequest, IsLikedResponse, MonitorRequest,
+    MonitorResponse, ServerInfoRequest, ServerInfoResponse, SetLikedRequest,
+    SetLikedResponse, ShutdownRequest, ShutdownResponse, ToggleLikedRequest,
+    ToggleLikedResponse, VersionRequest, VersionResponse,
 };
 use super::monitor_client::MonitorClient;
 use anyhow::Result;
 use futures::Stream;
-use tokio::sync::{self, broadcast};
+use tokio::sync::{self, mpsc, watch};
+use tokio::time::timeout;
 
-use tokio::sync::broadcast::Sender;
 use tonic::{Request, Response, Status};
 
-use super::wake_watcher::WakeWatcher;
+const GET_CURRENT_TIMEOUT: Duration = Duration::from_secs(5);
 
 type ResponseStream =
     Pin<Box<dyn Stream<Item = Result<MonitorResponse, Status>> + Send + Sync>>;
 
 pub struct MySpotifatius {
     liked_tracker: Arc<sync::Mutex<LikedTracker>>,
-    monitor_tx: Sender<MonitorResponse>,
-    wake_watcher: Arc<WakeWatcher>,
-    update_requests_tx: broadcast::Sender<()>,
+    /// The latest response which monitoring clients are subscribed to.
+    current_rx: watch::Receiver<Option<MonitorResponse>>,
+    /// The number of connected monitoring clients.
+    mirrors: Arc<AtomicUsize>,
+    started: Instant,
+    /// Asks the server to stop.
+    shutdown_tx: mpsc::Sender<()>,
 }
 
 impl MySpotifatius {
     pub fn new(
         liked_tracker: Arc<sync::Mutex<LikedTracker>>,
-        monitor_tx: Sender<MonitorResponse>,
-        wake_watcher: Arc<WakeWatcher>,
-        update_requests_tx: broadcast::Sender<()>,
+        current_rx: watch::Receiver<Option<MonitorResponse>>,
+        shutdown_tx: mpsc::Sender<()>,
     ) -> Self {
         MySpotifatius {
             liked_tracker,
-            monitor_tx,
-            wake_watcher,
-            update_requests_tx,
+            current_rx,
+            mirrors: Arc::new(AtomicUsize::new(0)),
+            started: Instant::now(),
+            shutdown_tx,
         }
     }
+
+    async fn set_liked(
+        &self,
+        request: Request<SetLikedRequest>,
+        liked: bool,
+    ) -> Result<Response<SetLikedResponse>, Status> {
+        let (changed, pending) = self
+            .liked_tracker
+            .lock()
+            .await
+            .set_liked(request.into_inner().track_id, liked)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        Ok(Response::new(SetLikedResponse { changed, pending }))
+    }
 }
 
 #[tonic::async_trait]
@@ -51,28 +78,116 @@ impl Spotifatius for MySpotifatius {
         &self,
         _request: Request<MonitorRequest>,
     ) -> Result<Response<Self::MonitorStream>, Status> {
-        let rx = self.monitor_tx.subscribe();
-        self.update_requests_tx
-            .send(())
-            .map_err(|err| Status::internal(err.to_string()))?;
-
-        Ok(Response::new(Box::pin(MonitorClient {
-            rx,
-            wake_watcher: self.wake_watcher.clone(),
-        }) as Self::MonitorStream))
+        Ok(Response::new(Box::pin(MonitorClient::new(
+            self.current_rx.clone(),
+            self.mirrors.clone(),
+        )) as Self::MonitorStream))
     }
 
     async fn toggle_liked(
         &self,
         _request: Request<ToggleLikedRequest>,
     ) -> Result<Response<ToggleLikedResponse>, Status> {
-        let is_liked = self
+        let (is_liked, pending) = self
             .liked_tracker
             .lock()
             .await
             .toggle_liked(None, false)
             .await
             .map_err(|err| Status::internal(err.to_string()))?;
-        Ok(Response::new(ToggleLikedResponse { is_liked }))
+        Ok(Response::new(ToggleLikedResponse { is_liked, pending }))
+    }
+
+    async fn like(
+        &self,
+        request: Request<SetLikedRequest>,
+    ) -> Result<Response<SetLikedResponse>, Status> {
+        self.set_liked(request, true).await
+    }
+
+    async fn unlike(
+        &self,
+        request: Request<SetLikedRequest>,
+    ) -> Result<Response<SetLikedResponse>, Status> {
+        self.set_liked(request, false).await
+    }
+
+    async fn is_liked(
+        &self,
+        request: Request<IsLikedRequest>,
+    ) -> Result<Response<IsLikedResponse>, Status> {
+        let (track_id, is_liked) = self
+            .liked_tracker
+            .lock()
+            .await
+            .is_liked(request.into_inner().track_id)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        Ok(Response::new(IsLikedResponse { track_id, is_liked }))
+    }
+
+    async fn get_current(
+        &self,
+        _request: Request<GetCurrentRequest>,
+    ) -> Result<Response<MonitorResponse>, Status> {
+        let mut current_rx = self.current_rx.clone();
+        // There is no response yet right after the server started.
+        let response = timeout(
+            GET_CURRENT_TIMEOUT,
+            current_rx.wait_for(|current| current.is_some()),
+        )
+        .await
+        .map_err(|_| {
+            Status::deadline_exceeded("No update received from Spotify.")
+        })?
+        .map_err(|err| Status::internal(err.to_string()))?
+        .clone()
+        .unwrap_or_default();
+        Ok(Response::new(response))
+    }
+
+    async fn server_info(
+        &self,
+        _request: Request<ServerInfoRequest>,
+    ) -> Result<Response<ServerInfoResponse>, Status> {
+        let player_owner = DBusClient::player_owner()
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        let tracker = self.liked_tracker.lock().await;
+        Ok(Response::new(ServerInfoResponse {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            pid: process::id(),
+            uptime_seconds: self.started.elapsed().as_secs(),
+            player_bus_name: SPOTIFY_DEST.to_string(),
+            player_owner,
+            mirrors: self.mirrors.load(Ordering::Relaxed) as u32,
+            cached_tracks: tracker.cached_count() as u32,
+            pending_changes: tracker.pending_count() as u32,
+            token_expires_at: tracker.token_expires_at().await,
+            last_api_error: tracker.last_error.clone(),
+        }))
+    }
+
+    async fn version(
+        &self,
+        _request: Request<VersionRequest>,
+    ) -> Result<Response<VersionResponse>, Status> {
+        Ok(Response::new(VersionResponse {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            pid: process::id(),
+        }))
+    }
+
+    async fn shutdown(
+        &self,
+        _request: Request<ShutdownRequest>,
+    ) -> Result<Response<ShutdownResponse>, Status> {
+        // The server stops after this response has been sent.
+        self.shutdown_tx
+            .send(())
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        Ok(Response::new(ShutdownResponse { pid: process::id() }))
     }
 }
@@ -0,0 +1,259 @@
+use std::{process::Stdio, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    process::{ChildStderr, Command},
+    select,
+    sync::Semaphore,
+    time::timeout,
+};
+use tracing::{debug, warn};
+
+use crate::server::{
+    album_art::AlbumArt,
+    grpc::api::{Track, TrackStatus},
+};
+use crate::shared::config::HooksConfig;
+
+/// How much of the end of stderr is kept to log when a hook fails.
+const MAX_STDERR_LEN: usize = 2048;
+/// How long stderr is still read after the hook exited, as processes it
+/// started in the background can keep it open.
+const STDERR_DRAIN_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    TrackChange,
+    StatusChange,
+    Liked,
+    Unliked,
+    Opened,
+    Closed,
+}
+
+impl HookEvent {
+    fn name(self) -> &'static str {
+        match self {
+            HookEvent::TrackChange => "track_change",
+            HookEvent::StatusChange => "status_change",
+            HookEvent::Liked => "liked",
+            HookEvent::Unliked => "unliked",
+            HookEvent::Opened => "opened",
+            HookEvent::Closed => "closed",
+        }
+    }
+}
+
+/// The event passed to hooks as JSON on stdin and environment variables.
+#[derive(Debug, Serialize)]
+struct HookData {
+    event: HookEvent,
+    status: String,
+    track_id: Option<String>,
+    artist: Option<String>,
+    title: Option<String>,
+    album: Option<String>,
+    art_url: Option<String>,
+    art_path: Option<String>,
+    is_ad: bool,
+    is_liked: Option<bool>,
+}
+
+impl HookData {
+    /// The `SPOTIFATIUS_*` environment variables, empty if unknown.
+    fn env(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("SPOTIFATIUS_EVENT", self.event.name().to_string()),
+            ("SPOTIFATIUS_STATUS", self.status.clone()),
+            (
+                "SPOTIFATIUS_TRACK_ID",
+                self.track_id.clone().unwrap_or_default(),
+            ),
+            (
+                "SPOTIFATIUS_ARTIST",
+                self.artist.clone().unwrap_or_default(),
+            ),
+            ("SPOTIFATIUS_TITLE", self.title.clone().unwrap_or_default()),
+            ("SPOTIFATIUS_ALBUM", self.album.clone().unwrap_or_default()),
+            (
+                "SPOTIFATIUS_ART_URL",
+                self.art_url.clone().unwrap_or_default(),
+            ),
+            (
+                "SPOTIFATIUS_ART_PATH",
+                self.art_path.clone().unwrap_or_default(),
+            ),
+            ("SPOTIFATIUS_IS_AD", self.is_ad.to_string()),
+            (
+                "SPOTIFATIUS_IS_LIKED",
+                self.is_liked
+                    .map(|is_liked| is_liked.to_string())
+                    .unwrap_or_default(),
+            ),
+        ]
+    }
+}
+
+/// Runs the commands configured in `[hooks]` in the background.
+///
+/// Hooks are killed after the timeout and at most `max_concurrent` run at
+/// the same time, so a slow hook never blocks the server.
+pub struct Hooks {
+    config: HooksConfig,
+    timeout: Duration,
+    permits: Arc<Semaphore>,
+    album_art: Option<Arc<AlbumArt>>,
+}
+
+impl Hooks {
+    pub fn new(
+        config: HooksConfig,
+        album_art: Option<Arc<AlbumArt>>,
+    ) -> Result<Self> {
+        Ok(Hooks {
+            timeout: config.timeout()?,
+            permits: Arc::new(Semaphore::new(config.max_concurrent.get())),
+            album_art,
+            config,
+        })
+    }
+
+    fn command(&self, event: HookEvent) -> Option<&String> {
+        match event {
+            HookEvent::TrackChange => self.config.track_change.as_ref(),
+            HookEvent::StatusChange => self.config.status_change.as_ref(),
+            HookEvent::Liked => self.config.liked.as_ref(),
+            HookEvent::Unliked => self.config.unliked.as_ref(),
+            HookEvent::Opened => self.config.opened.as_ref(),
+            HookEvent::Closed => self.config.closed.as_ref(),
+        }
+    }
+
+    /// Run the hook of the event if there is one, without waiting for it.
+    pub fn run(
+        &self,
+        event: HookEvent,
+        status: TrackStatus,
+        track: Option<&Track>,
+        is_liked: Option<bool>,
+    ) {
+        let Some(command) = self.command(event).cloned() else {
+            return;
+        };
+        let mut track = track.cloned();
+        let timeout = self.timeout;
+        let permits = self.permits.clone();
+        let album_art = self.album_art.clone();
+        tokio::spawn(async move {
+            // The semaphore is never closed.
+            let Ok(_permit) = permits.acquire_owned().await else {
+                return;
+            };
+            if let (Some(album_art), Some(track)) = (&album_art, &mut track) {
+                album_art.resolve(track).await;
+            }
+            let track = track.as_ref();
+            let data = HookData {
+                event,
+                status: status.into(),
+                track_id: track.and_then(|track| track.id.clone()),
+                artist: track.and_then(|track| track.artist.clone()),
+                title: track.and_then(|track| track.title.clone()),
+                album: track.and_then(|track| track.album.clone()),
+                art_url: track.and_then(|track| track.art_url.clone()),
+                art_path: track.and_then(|track| track.art_path.clone()),
+                is_ad: track.map_or(false, |track| track.is_ad),
+                is_liked,
+            };
+            debug!("Running {} hook: {command}", event.name());
+            if let Err(err) = run_hook(&command, &data, timeout).await {
+                warn!("The {} hook `{command}` failed: {err:#}", event.name());
+            }
+        });
+    }
+}
+
+async fn run_hook(
+    command: &str,
+    data: &HookData,
+    duration: Duration,
+) -> Result<()> {
+    let json = serde_json::to_vec(data)?;
+    // The output of the monitor command is read by bars, so hooks can't
+    // print to it. Their stderr is only logged when they fail.
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .envs(data.env())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("Could not start it")?;
+    let mut stdin = child.stdin.take().context("Could not open stdin")?;
+    let mut stderr = child.stderr.take().context("Could not open stderr")?;
+    let mut stderr_tail = vec![];
+    let result = timeout(duration, async {
+        // Hooks don't have to read stdin, so it may be closed already.
+        stdin.write_all(&json).await.ok();
+        drop(stdin);
+        // Keep reading stderr so the hook doesn't block on a full pipe.
+        let read_stderr = read_tail(&mut stderr, &mut stderr_tail);
+        tokio::pin!(read_stderr);
+        select! {
+            status = child.wait() => {
+                timeout(STDERR_DRAIN_TIMEOUT, &mut read_stderr).await.ok();
+                status
+            }
+            () = &mut read_stderr => child.wait().await,
+        }
+    })
+    .await;
+    match result {
+        Ok(status) => {
+            let status = status?;
+            if !status.success() {
+                return Err(failure(
+                    format!("exited with {status}"),
+                    &stderr_tail,
+                ));
+            }
+            Ok(())
+        }
+        // The child is killed when dropped.
+        Err(_) => Err(failure(
+            format!("timed out after {duration:?} and was killed"),
+            &stderr_tail,
+        )),
+    }
+}
+
+/// Read until the end, keeping the last `MAX_STDERR_LEN` bytes.
+async fn read_tail(stderr: &mut ChildStderr, tail: &mut Vec<u8>) {
+    let mut buffer = [0; 1024];
+    loop {
+        match stderr.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(len) => {
+                tail.extend_from_slice(&buffer[..len]);
+                let excess = tail.len().saturating_sub(MAX_STDERR_LEN);
+                tail.drain(..excess);
+            }
+        }
+    }
+}
+
+/// Add the end of the hook's stderr to the error, if it printed anything.
+fn failure(message: String, stderr_tail: &[u8]) -> anyhow::Error {
+    let stderr = String::from_utf8_lossy(stderr_tail);
+    let stderr = stderr.trim();
+    if stderr.is_empty() {
+        anyhow!(message)
+    } else {
+        anyhow!("{message}, its stderr ended with:\n{stderr}")
+    }
+}
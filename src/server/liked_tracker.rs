@@ -1,22 +1,34 @@
-use std::{collections::HashMap, fs::create_dir_all, path::PathBuf};
+use std::{collections::HashMap, fs, path::PathBuf};
 
 use anyhow::{Context, Result};
-use rspotify::{
-    clients::OAuthClient, model::TrackId, scopes, AuthCodeSpotify, Config,
-    Credentials, OAuth, DEFAULT_CACHE_PATH,
-};
+use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::Sender;
 use tracing::{debug, info, warn};
 
-use crate::shared::config::{resolve_home_path, DEFAULT_CONFIG_FOLDER};
+use crate::shared::config::{migrate_legacy_file, state_folder, SpotifyConfig};
 
+use super::backoff::is_transient;
 use super::grpc::api::ChangeEvent;
+use super::spotify_client::SpotifyClient;
+
+const PENDING_PATH: &str = ".pending_liked_changes.json";
 
 pub struct LikedTracker {
-    spotify: AuthCodeSpotify,
+    spotify: SpotifyClient,
     tracks: Tracks,
     pub current_track_id: Option<String>,
     change_tx: Sender<ChangeEvent>,
+    /// Like/unlike changes that could not be sent to Spotify yet, in order.
+    pending: Vec<PendingChange>,
+    pending_path: PathBuf,
+    /// The last error returned by the Spotify Web API.
+    pub last_error: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct PendingChange {
+    track_id: String,
+    liked: bool,
 }
 
 #[derive(Default)]
@@ -35,62 +47,67 @@ impl Tracks {
 }
 
 impl LikedTracker {
-    pub async fn new(change_tx: Sender<ChangeEvent>) -> Result<Self> {
-        let oauth = OAuth {
-            redirect_uri: "http://127.0.0.1:8000".to_string(),
-            scopes: scopes!("user-library-read", "user-library-modify"),
-            ..Default::default()
-        };
-        warn!("If you see a redirect error, please edit your Spotify app to allow the redirect URI: http://127.0.0.1:8000. This was changed in 0.3.0. See https://github.com/AndreasBackx/spotifatius/pull/9.");
-        let creds = Credentials::from_env().context(
-            "Could not find RSPOTIFY_CLIENT_(ID|SECRET) environment variables",
-        )?;
-        let cache_folder =
-            resolve_home_path(PathBuf::from(DEFAULT_CONFIG_FOLDER))?;
-        create_dir_all(cache_folder.clone()).with_context(|| {
-            format!("Could not create cache folder {cache_folder:?}")
-        })?;
-        let cache_path = cache_folder.join(DEFAULT_CACHE_PATH);
-        // error!("{cache_path}");
-        let config = Config {
-            token_cached: true,
-            token_refreshing: true,
-            cache_path,
-            ..Default::default()
-        };
-        let spotify = AuthCodeSpotify::with_config(creds, oauth, config);
+    pub async fn new(
+        change_tx: Sender<ChangeEvent>,
+        spotify_config: &SpotifyConfig,
+    ) -> Result<Self> {
+        let spotify = SpotifyClient::new(spotify_config)?;
+        spotify.load_token().await?;
 
-        let url = spotify.get_authorize_url(true)?;
-        spotify.prompt_for_token(&url).await?; // This is where it crashes.
+        let pending_path = state_folder()?.join(PENDING_PATH);
+        migrate_legacy_file(&pending_path)?;
+        let pending = read_pending(&pending_path)?;
+        let mut tracks = Tracks::default();
+        for change in &pending {
+            tracks.add(change.track_id.clone(), change.liked);
+        }
 
         Ok(LikedTracker {
             spotify,
-            tracks: Tracks::default(),
+            tracks,
             current_track_id: None,
             change_tx,
+            pending,
+            pending_path,
+            last_error: None,
         })
     }
 
     pub async fn save(&mut self, track_id: String) -> Result<()> {
-        self.spotify
-            .current_user_saved_tracks_add(
-                [TrackId::from_id(&track_id)?].into_iter(),
-            )
-            .await?;
+        let result = self.spotify.saved_tracks_add(&track_id).await;
+        self.record_error(result)?;
         self.tracks.add(track_id, true);
         Ok(())
     }
 
     pub async fn remove(&mut self, track_id: String) -> Result<()> {
-        self.spotify
-            .current_user_saved_tracks_delete(
-                [TrackId::from_id(&track_id)?].into_iter(),
-            )
-            .await?;
+        let result = self.spotify.saved_tracks_delete(&track_id).await;
+        self.record_error(result)?;
         self.tracks.add(track_id, false);
         Ok(())
     }
 
+    fn record_error<T>(&mut self, result: Result<T>) -> Result<T> {
+        if let Err(err) = &result {
+            self.last_error = Some(format!("{err:#}"));
+        }
+        result
+    }
+
+    /// The number of tracks of which the liked state is cached.
+    pub fn cached_count(&self) -> usize {
+        self.tracks.liked.len()
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// When the access token expires in RFC 3339 format.
+    pub async fn token_expires_at(&self) -> Option<String> {
+        self.spotify.token_expires_at().await
+    }
+
     pub fn is_liked_cached(&self, track_id: &String) -> Option<bool> {
         self.tracks.is_liked(track_id)
     }
@@ -100,43 +117,188 @@ impl LikedTracker {
         track_id: String,
         force_refresh: bool,
     ) -> Result<bool> {
-        if !force_refresh {
+        if !force_refresh || self.is_pending(&track_id) {
             if let Some(liked) = self.is_liked_cached(&track_id) {
                 debug!("{} is cached: {}.", track_id, liked);
                 return Ok(liked);
             }
         }
-        let liked = self
-            .spotify
-            .current_user_saved_tracks_contains(
-                [TrackId::from_id(&track_id)?].into_iter(),
-            )
-            .await
-            .map(|liked| liked[0])?;
+        let result = self.spotify.saved_tracks_contains(&track_id).await;
+        let liked = self.record_error(result)?;
         debug!("{} is liked: {}", track_id, liked);
 
         self.tracks.add(track_id, liked);
         Ok(liked)
     }
 
+    /// Toggle the liked state of the given or current track.
+    ///
+    /// If Spotify can't be reached, the change is applied to the cache and
+    /// queued to be sent later with `replay_pending`. Returns the new liked
+    /// state and whether it is pending.
     pub async fn toggle_liked(
         &mut self,
         track_id_or_current: Option<String>,
         force_refresh: bool,
-    ) -> Result<bool> {
-        let track_id = track_id_or_current
+    ) -> Result<(bool, bool)> {
+        let track_id = self.track_id_or_current(track_id_or_current)?;
+        let liked = !self.liked_state(&track_id, force_refresh).await?;
+        let pending = self.apply_liked(track_id, liked).await?;
+        Ok((liked, pending))
+    }
+
+    /// Set the liked state of the given or current track.
+    ///
+    /// Nothing is changed if the track already has that state. Returns
+    /// whether it changed and whether the change is pending.
+    pub async fn set_liked(
+        &mut self,
+        track_id_or_current: Option<String>,
+        liked: bool,
+    ) -> Result<(bool, bool)> {
+        let track_id = self.track_id_or_current(track_id_or_current)?;
+        // It could have changed in another client since it was cached.
+        if self.liked_state(&track_id, true).await? == liked {
+            return Ok((false, self.is_pending(&track_id)));
+        }
+        let pending = self.apply_liked(track_id, liked).await?;
+        Ok((true, pending))
+    }
+
+    /// Get the liked state of the given or current track together with its ID.
+    pub async fn is_liked(
+        &mut self,
+        track_id_or_current: Option<String>,
+    ) -> Result<(String, bool)> {
+        let track_id = self.track_id_or_current(track_id_or_current)?;
+        let is_liked = self.liked_state(&track_id, false).await?;
+        Ok((track_id, is_liked))
+    }
+
+    fn track_id_or_current(
+        &self,
+        track_id_or_current: Option<String>,
+    ) -> Result<String> {
+        track_id_or_current
             .or_else(|| self.current_track_id.clone())
-            .context("no current track playing")?;
-        if !self.check_liked(track_id.clone(), force_refresh).await? {
+            .context("no current track playing")
+    }
+
+    /// Get the liked state, falling back to the cache if Spotify can't be
+    /// reached.
+    async fn liked_state(
+        &mut self,
+        track_id: &String,
+        force_refresh: bool,
+    ) -> Result<bool> {
+        match self.check_liked(track_id.clone(), force_refresh).await {
+            Ok(is_liked) => Ok(is_liked),
+            Err(err) => self.is_liked_cached(track_id).with_context(|| {
+                format!("could not get the liked state of {track_id}: {err}")
+            }),
+        }
+    }
+
+    /// Save or remove the track, queueing it if Spotify can't be reached.
+    /// Returns whether the change is pending.
+    async fn apply_liked(
+        &mut self,
+        track_id: String,
+        liked: bool,
+    ) -> Result<bool> {
+        let result = if liked {
             info!("Saving");
-            self.save(track_id).await?;
-            self.change_tx.send(ChangeEvent::TrackLiked(true)).await?;
-            Ok(true)
+            self.save(track_id.clone()).await
         } else {
             info!("Removing");
-            self.remove(track_id).await?;
-            self.change_tx.send(ChangeEvent::TrackLiked(false)).await?;
-            Ok(false)
+            self.remove(track_id.clone()).await
+        };
+        let pending = match result {
+            Ok(()) => false,
+            Err(err) if is_transient(&err) => {
+                warn!("Could not update {track_id}, queueing it: {err:#}");
+                self.queue_pending(track_id.clone(), liked)?;
+                self.change_tx.send(ChangeEvent::PendingQueued).await?;
+                true
+            }
+            Err(err) => return Err(err),
+        };
+        // Only show the liked message when it's about what's playing.
+        if self.current_track_id.as_ref() == Some(&track_id) {
+            self.change_tx.send(ChangeEvent::TrackLiked(liked)).await?;
+        }
+        Ok(pending)
+    }
+
+    /// Whether the track has a liked change that wasn't sent to Spotify yet.
+    pub fn is_pending(&self, track_id: &String) -> bool {
+        self.pending
+            .iter()
+            .any(|change| &change.track_id == track_id)
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    fn queue_pending(&mut self, track_id: String, liked: bool) -> Result<()> {
+        // Saving and removing is idempotent so only the last change matters.
+        self.pending.retain(|change| change.track_id != track_id);
+        self.pending.push(PendingChange {
+            track_id: track_id.clone(),
+            liked,
+        });
+        self.tracks.add(track_id, liked);
+        self.write_pending()
+    }
+
+    /// Send the queued liked changes to Spotify in the order they were made.
+    ///
+    /// Changes that Spotify rejects are dropped so they don't block the rest,
+    /// it stops at the first error that could succeed when retried.
+    pub async fn replay_pending(&mut self) -> Result<()> {
+        while let Some(change) = self.pending.first().cloned() {
+            let result = if change.liked {
+                self.save(change.track_id.clone()).await
+            } else {
+                self.remove(change.track_id.clone()).await
+            };
+            match result {
+                Ok(()) => {
+                    info!("Synced pending change for {}", change.track_id)
+                }
+                Err(err) if is_transient(&err) => return Err(err),
+                Err(err) => {
+                    warn!(
+                        "Dropping pending change for {}: {err:#}",
+                        change.track_id
+                    );
+                    // The cached state is that of the change, so get it
+                    // from Spotify again.
+                    self.tracks.liked.remove(&change.track_id);
+                }
+            }
+            self.pending.remove(0);
+            self.write_pending()?;
         }
+        Ok(())
+    }
+
+    /// Write the queue of pending changes to disk.
+    pub fn write_pending(&self) -> Result<()> {
+        let content = serde_json::to_string(&self.pending)?;
+        fs::write(&self.pending_path, content).with_context(|| {
+            format!("Could not write {}", self.pending_path.display())
+        })
+    }
+}
+
+fn read_pending(path: &PathBuf) -> Result<Vec<PendingChange>> {
+    if !path.exists() {
+        return Ok(vec![]);
     }
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Could not parse {}", path.display()))
 }
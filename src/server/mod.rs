@@ -1,4 +1,10 @@
+pub mod album_art;
+pub mod backoff;
 pub mod dbus;
 pub mod grpc;
+pub mod hooks;
 pub mod liked_tracker;
+pub mod notifications;
 pub mod service;
+pub mod spotify_client;
+pub mod systemd;
@@ -0,0 +1,232 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+use zbus::{Connection, Proxy};
+use zvariant::Value;
+
+use crate::server::{
+    album_art::AlbumArt,
+    dbus::{SPOTIFY_DEST, SPOTIFY_PATH},
+    grpc::api::Track,
+    liked_tracker::LikedTracker,
+};
+use crate::shared::config::{MessagesConfig, NotificationsConfig};
+
+const NOTIFICATIONS_DEST: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+const NOTIFICATIONS_INTERFACE: &str = "org.freedesktop.Notifications";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+const APP_NAME: &str = "spotifatius";
+
+/// The notification that is currently shown.
+#[derive(Default)]
+struct Shown {
+    /// ID given by the notification server, used to replace it.
+    id: u32,
+    track_id: Option<String>,
+}
+
+/// Sends desktop notifications and handles their actions.
+///
+/// The D-Bus session connection of the server is shared for the
+/// notifications, their actions and skipping tracks.
+pub struct Notifier {
+    connection: Connection,
+    config: NotificationsConfig,
+    messages: MessagesConfig,
+    shown: Mutex<Shown>,
+    album_art: Option<Arc<AlbumArt>>,
+}
+
+impl Notifier {
+    pub fn new(
+        connection: Connection,
+        config: NotificationsConfig,
+        messages: MessagesConfig,
+        album_art: Option<Arc<AlbumArt>>,
+    ) -> Self {
+        Notifier {
+            connection,
+            config,
+            messages,
+            shown: Mutex::new(Shown::default()),
+            album_art,
+        }
+    }
+
+    /// Notify that a different track started, in the background.
+    pub fn track_changed(
+        self: &Arc<Self>,
+        track: &Track,
+        is_liked: Option<bool>,
+    ) {
+        let summary = render(&self.config.summary, track, "");
+        let body = render(&self.config.body, track, "");
+        self.spawn_notify(track, is_liked, summary, body);
+    }
+
+    /// Notify that the current track was liked or unliked, in the background.
+    pub fn liked_changed(self: &Arc<Self>, track: &Track, is_liked: bool) {
+        let message = if is_liked {
+            &self.messages.added
+        } else {
+            &self.messages.removed
+        };
+        let summary = render(&self.config.liked_summary, track, message);
+        let body = render(&self.config.liked_body, track, message);
+        self.spawn_notify(track, Some(is_liked), summary, body);
+    }
+
+    fn spawn_notify(
+        self: &Arc<Self>,
+        track: &Track,
+        is_liked: Option<bool>,
+        summary: String,
+        body: String,
+    ) {
+        // Skip ads and tracks without metadata yet, like right after Spotify
+        // opens.
+        if track.is_ad || track.title.is_none() {
+            return;
+        }
+        let notifier = self.clone();
+        let mut track = track.clone();
+        tokio::spawn(async move {
+            if let Some(album_art) = &notifier.album_art {
+                album_art.resolve(&mut track).await;
+            }
+            if let Err(err) =
+                notifier.notify(&track, is_liked, &summary, &body).await
+            {
+                warn!("Could not send a notification: {err:#}");
+            }
+        });
+    }
+
+    async fn notifications(&self) -> Result<Proxy<'_>> {
+        Ok(Proxy::new(
+            &self.connection,
+            NOTIFICATIONS_DEST,
+            NOTIFICATIONS_PATH,
+            NOTIFICATIONS_INTERFACE,
+        )
+        .await?)
+    }
+
+    /// Send the notification, replacing the previous one.
+    async fn notify(
+        &self,
+        track: &Track,
+        is_liked: Option<bool>,
+        summary: &str,
+        body: &str,
+    ) -> Result<()> {
+        let mut actions = vec![];
+        if self.config.actions {
+            // Actions are pairs of a key and a label.
+            if is_liked == Some(true) {
+                actions.extend(["unlike", "Unlike"]);
+            } else {
+                actions.extend(["like", "Like"]);
+            }
+            actions.extend(["skip", "Skip"]);
+        }
+        let hints: HashMap<&str, Value> = HashMap::new();
+        // Not all notification servers support URLs.
+        let icon = track
+            .art_path
+            .as_deref()
+            .or(track.art_url.as_deref())
+            .unwrap_or_default();
+
+        // Keep it locked so notifications sent at the same time don't both
+        // replace the same one.
+        let mut shown = self.shown.lock().await;
+        let id: u32 = self
+            .notifications()
+            .await?
+            .call(
+                "Notify",
+                &(
+                    APP_NAME,
+                    shown.id,
+                    icon,
+                    summary,
+                    body,
+                    actions,
+                    hints,
+                    self.config.expire_timeout,
+                ),
+            )
+            .await
+            .context("Could not call Notify")?;
+        *shown = Shown {
+            id,
+            track_id: track.id.clone(),
+        };
+        Ok(())
+    }
+
+    /// Handle the actions of the notifications until the connection closes.
+    pub async fn listen_actions(
+        self: Arc<Self>,
+        liked_tracker: Arc<Mutex<LikedTracker>>,
+    ) -> Result<()> {
+        let notifications = self.notifications().await?;
+        let mut action_stream =
+            notifications.receive_signal("ActionInvoked").await?;
+        while let Some(signal) = action_stream.next().await {
+            let (id, action): (u32, String) = signal.body()?;
+            // The signal is sent for the notifications of all applications.
+            let track_id = {
+                let shown = self.shown.lock().await;
+                if shown.id != id {
+                    continue;
+                }
+                shown.track_id.clone()
+            };
+            debug!("Notification action invoked: {action}");
+            let result = match action.as_str() {
+                "like" | "unlike" => liked_tracker
+                    .lock()
+                    .await
+                    .set_liked(track_id, action == "like")
+                    .await
+                    .map(|_| ()),
+                "skip" => self.skip().await,
+                _ => Ok(()),
+            };
+            if let Err(err) = result {
+                warn!("Could not handle the {action} action: {err:#}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Skip to the next track in Spotify.
+    async fn skip(&self) -> Result<()> {
+        let player = Proxy::new(
+            &self.connection,
+            SPOTIFY_DEST,
+            SPOTIFY_PATH,
+            PLAYER_INTERFACE,
+        )
+        .await?;
+        player
+            .call::<_, _, ()>("Next", &())
+            .await
+            .context("Could not skip to the next track")
+    }
+}
+
+/// Replace the placeholders in the notification template.
+fn render(template: &str, track: &Track, message: &str) -> String {
+    template
+        .replace("{artist}", track.artist.as_deref().unwrap_or_default())
+        .replace("{title}", track.title.as_deref().unwrap_or_default())
+        .replace("{album}", track.album.as_deref().unwrap_or_default())
+        .replace("{message}", message)
+}
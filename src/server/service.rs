@@ -1,119 +1,404 @@
-use std::error::Error;
-
-use std::{sync::Arc, time::Duration};
+use std::{
+    future::{self, Future},
+    io, mem,
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::{Context, Result};
 
+use tokio::net::TcpListener;
 use tokio::sync::Mutex;
 use tokio::sync::{
     broadcast,
     mpsc::{self},
+    oneshot, watch,
 };
-use tokio::time::Instant;
+use tokio::time::{timeout, Instant, Interval};
 use tokio::{self, time};
-use tokio_stream::StreamExt;
+use tokio_stream::{wrappers::TcpListenerStream, StreamExt};
 use tonic::transport::Server;
-use tracing::debug;
+use tracing::{debug, info, warn};
+use zbus::Connection;
 
+use crate::client::service::Service as ClientService;
 use crate::server::grpc::server::MySpotifatius;
 use crate::server::{
+    album_art::AlbumArt,
+    backoff::Backoff,
     dbus::DBusClient,
     grpc::api::{
-        spotifatius_client::SpotifatiusClient,
         spotifatius_server::SpotifatiusServer, MonitorRequest, MonitorResponse,
-        Track, TrackStatus,
+        Track, TrackChange, TrackStatus,
     },
+    hooks::{HookEvent, Hooks},
     liked_tracker::LikedTracker,
+    notifications::Notifier,
+    systemd,
 };
 
 use super::grpc::api::ChangeEvent;
-use super::grpc::wake_watcher::WakeWatcher;
 
+use crate::shared::config::Config;
 use crate::shared::consts::ADDRESS;
 
+/// How long monitoring clients get to receive the stopping response.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+/// Mirrors have to notice a restarted server quickly, the server is local so
+/// retrying often is cheap.
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(5);
+
 pub struct Service {
+    /// Only used once serving, so mirrors don't need Spotify credentials.
+    config: Config,
     monitor_tx: broadcast::Sender<MonitorResponse>,
-    liked_tracker: Arc<Mutex<LikedTracker>>,
     change_tx: mpsc::Sender<ChangeEvent>,
     change_rx: mpsc::Receiver<ChangeEvent>,
-    wake_watcher: Arc<WakeWatcher>,
+    /// The latest response, monitoring gRPC clients are subscribed to this.
+    current_tx: watch::Sender<Option<MonitorResponse>>,
+    backoff: Backoff,
+    /// Whether the Spotify Web API was unreachable on the last request.
+    offline: bool,
+    /// Whether there are liked changes queued to be sent to Spotify.
+    has_pending: bool,
+    /// How long the added or removed message is shown.
+    toast_duration: Duration,
+    /// Only set while serving.
+    hooks: Option<Hooks>,
+    /// Only set while serving if notifications are enabled.
+    notifier: Option<Arc<Notifier>>,
+    /// Only set while serving if album art is enabled.
+    album_art: Option<Arc<AlbumArt>>,
+    /// The last track change that hooks and notifications were run for.
+    announced: Option<TrackChange>,
 }
 
 impl Service {
-    pub async fn new(
+    pub fn new(
         monitor_tx: broadcast::Sender<MonitorResponse>,
+        config: &Config,
     ) -> Result<Self> {
         let (change_tx, change_rx) = mpsc::channel::<ChangeEvent>(100);
-
-        let liked_tracker =
-            Arc::new(Mutex::new(LikedTracker::new(change_tx.clone()).await?));
-        let wake_watcher = Arc::new(WakeWatcher::new());
+        let (current_tx, _) = watch::channel(None);
 
         Ok(Service {
+            config: config.clone(),
             monitor_tx,
-            liked_tracker,
             change_tx,
             change_rx,
-            wake_watcher,
+            current_tx,
+            backoff: Backoff::new(),
+            offline: false,
+            has_pending: false,
+            toast_duration: config.toast.duration()?,
+            hooks: None,
+            notifier: None,
+            album_art: None,
+            announced: None,
         })
     }
 
-    fn send_and_wake(&self, value: MonitorResponse) -> Result<usize> {
-        let result = self.monitor_tx.send(value)?;
-        self.wake_watcher.wake()?;
-        Ok(result)
+    /// Set the path of the album art if it's cached, otherwise download it in
+    /// the background and send `AlbumArtCached` once it's done.
+    fn resolve_album_art(&self, track: &mut Track) {
+        let (Some(album_art), Some(url)) = (&self.album_art, &track.art_url)
+        else {
+            return;
+        };
+        if let Some(path) = album_art.cached_path(url) {
+            track.art_path = Some(path.display().to_string());
+            return;
+        }
+        let album_art = album_art.clone();
+        let url = url.clone();
+        let change_tx = self.change_tx.clone();
+        tokio::spawn(async move {
+            match album_art.get(&url).await {
+                Ok(path) => {
+                    let event = ChangeEvent::AlbumArtCached(
+                        url,
+                        path.display().to_string(),
+                    );
+                    change_tx.send(event).await.ok();
+                }
+                Err(err) => warn!("Could not get the album art: {err:#}"),
+            }
+        });
     }
 
-    async fn monitor_client(&mut self) -> Result<()> {
-        let mut client =
-            SpotifatiusClient::connect(format!("http://{ADDRESS}")).await?;
+    /// Run the hooks and send a notification if the track or status changed.
+    ///
+    /// Tracks are only announced once their title and artist are known, as
+    /// the first change after Spotify opens only has the ID.
+    fn announce_change(
+        &mut self,
+        current: Option<&TrackChange>,
+        is_liked: Option<bool>,
+    ) {
+        if current.map_or(false, |track_change| {
+            track_change.track.title.is_none()
+                || track_change.track.artist.is_none()
+        }) {
+            return;
+        }
+        let last = mem::replace(&mut self.announced, current.cloned());
+        let last = last.as_ref();
+        let track = current.map(|track_change| &track_change.track);
+        let status = current
+            .map_or(TrackStatus::Stopped, |track_change| track_change.status);
+        if track.is_some()
+            && last.and_then(|last| last.track.id.as_ref())
+                != track.and_then(|track| track.id.as_ref())
+        {
+            self.run_hook(HookEvent::TrackChange, status, track, is_liked);
+            if let (Some(notifier), Some(track)) = (&self.notifier, track) {
+                notifier.track_changed(track, is_liked);
+            }
+        }
+        let last_status = last
+            .map_or(TrackStatus::Stopped, |track_change| track_change.status);
+        if last_status != status {
+            self.run_hook(HookEvent::StatusChange, status, track, is_liked);
+        }
+    }
+
+    fn run_hook(
+        &self,
+        event: HookEvent,
+        status: TrackStatus,
+        track: Option<&Track>,
+        is_liked: Option<bool>,
+    ) {
+        if let Some(hooks) = &self.hooks {
+            hooks.run(event, status, track, is_liked);
+        }
+    }
+
+    /// Send the response to all monitoring clients unless it's the same as
+    /// the latest one.
+    ///
+    /// Failing to do so is logged rather than returned as it should not stop
+    /// the server.
+    fn send(&self, value: MonitorResponse) {
+        if self.current_tx.borrow().as_ref() == Some(&value) {
+            debug!("Skip sending monitor response as it was the same");
+            return;
+        }
+        self.current_tx.send_replace(Some(value.clone()));
+        // There are no receivers when running as daemon.
+        if let Err(err) = self.monitor_tx.send(value) {
+            debug!("Could not send monitor response: {err}");
+        }
+    }
+
+    /// Mirror the responses of the server until it closes, returns whether
+    /// it was stopped on purpose.
+    async fn monitor_client(
+        &mut self,
+        reconnect_backoff: &mut Backoff,
+    ) -> Result<bool> {
+        let mut client = ClientService::connect().await?;
         let request = tonic::Request::new(MonitorRequest {});
         let mut stream = client.monitor(request).await?.into_inner();
+        reconnect_backoff.reset();
 
         while let Some(item) = stream.next().await {
+            let response = item?;
+            if response.stopping {
+                return Ok(true);
+            }
             self.monitor_tx
-                .send(item?)
+                .send(response)
                 .context("Could not forward gRPC stream")?;
         }
 
-        Ok(())
+        Ok(false)
+    }
+
+    /// Serve the changes if no other server is running, otherwise mirror it.
+    ///
+    /// Binding the server address doubles as the lock that decides which
+    /// instance is the server. When the server closes, the mirrors reconnect
+    /// and the first one to bind the address takes over as the server. When
+    /// it was stopped on purpose, mirrors exit instead if
+    /// `exit_on_server_stop` is set.
+    pub async fn monitor(&mut self, exit_on_server_stop: bool) -> Result<()> {
+        let mut reconnect_backoff =
+            Backoff::with_max_delay(RECONNECT_MAX_DELAY);
+        loop {
+            match TcpListener::bind(ADDRESS).await {
+                Ok(listener) => {
+                    return self.serve(listener, future::pending()).await
+                }
+                Err(err) if err.kind() == io::ErrorKind::AddrInUse => {
+                    debug!("Server address is in use, mirroring it");
+                }
+                Err(err) => {
+                    return Err(err)
+                        .context("Could not bind the server address")
+                }
+            }
+            match self.monitor_client(&mut reconnect_backoff).await {
+                Ok(true) if exit_on_server_stop => {
+                    info!("The server stopped, exiting...");
+                    return Ok(());
+                }
+                Ok(true) => {
+                    info!("The server stopped, taking over...");
+                    continue;
+                }
+                Ok(false) => info!("The server closed, reconnecting..."),
+                Err(err) => warn!("Could not mirror the server: {err:#}"),
+            }
+            time::sleep(reconnect_backoff.next_delay()).await;
+        }
+    }
+
+    /// Only serve the changes, used by the `daemon` command.
+    ///
+    /// The listener is either passed by systemd socket activation or bound
+    /// here, failing if another server is already running. It stops
+    /// gracefully when the `shutdown` future completes.
+    pub async fn daemon(
+        &mut self,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<()> {
+        let listener = match systemd::activated_listener()? {
+            Some(listener) => TcpListener::from_std(listener)?,
+            None => TcpListener::bind(ADDRESS).await.with_context(|| {
+                format!(
+                    "Could not bind {ADDRESS}, is a server already running?"
+                )
+            })?,
+        };
+        self.serve(listener, shutdown).await
     }
 
-    pub async fn monitor(&mut self) -> Result<()> {
+    /// Create the notifier if notifications are enabled.
+    fn start_notifier(&self, connection: &Connection) -> Option<Arc<Notifier>> {
+        if !self.config.notifications.enabled {
+            return None;
+        }
+        Some(Arc::new(Notifier::new(
+            connection.clone(),
+            self.config.notifications.clone(),
+            self.config.messages.clone(),
+            self.album_art.clone(),
+        )))
+    }
+
+    /// Serve the changes until stopped with `spotifatius stop` or the
+    /// `shutdown` future completes.
+    async fn serve(
+        &mut self,
+        listener: TcpListener,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<()> {
+        // Only the server talks to Spotify, so this is done after binding.
+        let liked_tracker = Arc::new(Mutex::new(
+            LikedTracker::new(self.change_tx.clone(), &self.config.spotify)
+                .await?,
+        ));
+        self.has_pending = liked_tracker.lock().await.has_pending();
+        if self.config.album_art.enabled {
+            self.album_art =
+                Some(Arc::new(AlbumArt::new(&self.config.album_art)?));
+        }
+        self.hooks = Some(Hooks::new(
+            self.config.hooks.clone(),
+            self.album_art.clone(),
+        )?);
+
+        // Shared by listening to Spotify and the notifications.
+        let connection = Connection::session()
+            .await
+            .context("Could not connect to the D-Bus session bus")?;
         let (update_requests_tx, _) = broadcast::channel::<()>(1);
+        let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
+        let (stop_rpc_tx, stop_rpc_rx) = oneshot::channel::<()>();
         let change_tx = self.change_tx.clone();
+        let shutdown_handle = {
+            let shutdown_tx = shutdown_tx.clone();
+            tokio::spawn(async move {
+                shutdown.await;
+                shutdown_tx.send(()).await.ok();
+            })
+        };
         let rpc = MySpotifatius::new(
-            self.liked_tracker.clone(),
-            self.monitor_tx.clone(),
-            self.wake_watcher.clone(),
-            update_requests_tx.clone(),
+            liked_tracker.clone(),
+            self.current_tx.subscribe(),
+            shutdown_tx,
         );
 
-        let mut dbus = DBusClient::new(change_tx, update_requests_tx.clone());
+        let mut dbus = DBusClient::new(
+            connection.clone(),
+            change_tx,
+            update_requests_tx.clone(),
+        );
         let mut dbus_handle = tokio::spawn(async move { dbus.listen().await });
 
-        let addr = ADDRESS.parse()?;
+        self.notifier = self.start_notifier(&connection);
+        let notifier = self
+            .notifier
+            .clone()
+            .filter(|_| self.config.notifications.actions);
+        let actions_liked_tracker = liked_tracker.clone();
+        let actions_handle = tokio::spawn(async move {
+            let Some(notifier) = notifier else {
+                return;
+            };
+            // Notifications are optional, so failing to handle their actions
+            // doesn't stop the server.
+            if let Err(err) =
+                notifier.listen_actions(actions_liked_tracker).await
+            {
+                warn!("Could not handle notification actions: {err:#}");
+            }
+        });
+
         let mut rpc_handle = tokio::spawn(async move {
             Server::builder()
                 .add_service(SpotifatiusServer::new(rpc))
-                .serve(addr)
+                .serve_with_incoming_shutdown(
+                    TcpListenerStream::new(listener),
+                    async {
+                        stop_rpc_rx.await.ok();
+                    },
+                )
                 .await
         });
 
+        // Only the daemon is started by systemd with `Type=notify`.
+        systemd::notify("READY=1")?;
+
         let mut interval = time::interval_at(
             Instant::now() + Duration::from_secs(3600),
             Duration::from_secs(3600),
         );
+        // Used to retry requests to the Spotify Web API while it's unreachable
+        // or while there are liked changes queued from a previous run.
+        let mut retry_interval = time::interval_at(
+            Instant::now()
+                + if self.has_pending {
+                    Duration::ZERO
+                } else {
+                    Duration::from_secs(3600)
+                },
+            Duration::from_secs(3600),
+        );
+        let mut last_track_change: Option<TrackChange> = None;
 
         loop {
             tokio::select! {
                 Some(change_event) = self.change_rx.recv() => {
                     debug!("Received {:#?}", change_event);
-                    let mut tracker = self.liked_tracker.lock().await;
+                    let mut tracker = liked_tracker.lock().await;
                     match change_event {
-                        ChangeEvent::TrackChange(track_change) => {
-                            tracker.current_track_id = track_change.track.id.clone();
-                            if let Some(track_id) = track_change.track.id {
+                        ChangeEvent::TrackChange(mut track_change) => {
+                            self.resolve_album_art(&mut track_change.track);
+                            // Ads can't be liked.
+                            tracker.current_track_id = track_change.track.id.clone().filter(|_| !track_change.track.is_ad);
+                            if let Some(track_id) = track_change.track.id.clone() {
                                 // If there's an interval running to request an update,
                                 // cancel it because it's no longer needed.
                                 // Only if the current title/artist are not empty,
@@ -125,31 +410,42 @@ impl Service {
 
                                 let is_cached_liked = tracker.is_liked_cached(&track_id);
 
-                                self.send_and_wake(MonitorResponse {
-                                    track:Some( Track { id: Some(track_id.clone()), artist: track_change.track.artist.clone(), title: track_change.track.title.clone(), album: track_change.track.album.clone() }),
-                                    status: track_change.status.into(),
-                                    is_liked: is_cached_liked,
-                                })?;
+                                self.send(track_response(
+                                    &track_change,
+                                    is_cached_liked,
+                                    is_cached_liked.is_none() && self.offline,
+                                    tracker.is_pending(&track_id),
+                                ));
 
-                                if is_cached_liked.is_none() {
+                                if is_cached_liked.is_none() && !track_change.track.is_ad {
                                     debug!("Save status wasn't cached yet, caching it now!");
-                                    let is_liked = tracker.check_liked(track_id.clone(), false).await?;
-
-                                    if is_liked {
-                                        debug!("New monitor response because is_liked went from unknown to true");
-                                        self.send_and_wake( MonitorResponse {
-                                            track:Some( Track { id: Some(track_id), artist: track_change.track.artist, title: track_change.track.title, album: track_change.track.album }),
-                                            status: track_change.status.into(),
-                                            is_liked: Some(is_liked),
-                                        })?;
+                                    match tracker.check_liked(track_id, false).await {
+                                        Ok(is_liked) => {
+                                            if is_liked || self.offline {
+                                                debug!("New monitor response because is_liked went from unknown to known");
+                                                self.send(track_response(&track_change, Some(is_liked), false, false));
+                                            }
+                                            self.offline = false;
+                                            self.backoff.reset();
+                                        }
+                                        Err(err) => {
+                                            let delay = self.backoff.next_delay_after(&err);
+                                            warn!("Could not check liked status, retrying in {delay:?}: {err:#}");
+                                            restart_after(&mut retry_interval, delay);
+                                            if !self.offline {
+                                                self.offline = true;
+                                                self.send(track_response(&track_change, None, true, false));
+                                            }
+                                        }
                                     }
                                 }
+                                let is_liked = track_change.track.id.as_ref().and_then(|track_id| tracker.is_liked_cached(track_id));
+                                self.announce_change(Some(&track_change), is_liked);
+                                last_track_change = Some(track_change);
                             } else {
-                                self.send_and_wake( MonitorResponse {
-                                    track: None,
-                                    status: TrackStatus::Stopped.into(),
-                                    is_liked: None,
-                                })?;
+                                self.announce_change(None, None);
+                                last_track_change = None;
+                                self.send(stopped_response());
                             };
                         }
                         ChangeEvent::SpotifyOpened => {
@@ -157,23 +453,101 @@ impl Service {
                             // not have an artist/title. Immedaitely asking for
                             // an update will not work, but waiting ~0.2s will.
                             // We'll use 0.5s just in case.
-                            interval = time::interval_at(Instant::now() + Duration::from_millis(500), interval.period());
+                            restart_after(&mut interval, Duration::from_millis(500));
+                            self.run_hook(HookEvent::Opened, TrackStatus::Stopped, None, None);
                         }
                         ChangeEvent::SpotifyClosed => {
                             tracker.current_track_id = None;
-                            self.send_and_wake( MonitorResponse {
-                                track: None,
-                                status: TrackStatus::Stopped.into(),
-                                is_liked: None,
-                            })?;
+                            self.announce_change(None, None);
+                            self.run_hook(HookEvent::Closed, TrackStatus::Stopped, None, None);
+                            last_track_change = None;
+                            self.send(stopped_response());
+                        }
+                        ChangeEvent::AlbumArtCached(art_url, art_path) => {
+                            // The track could have changed during the download.
+                            let has_art = |track: &Track| track.art_url.as_ref() == Some(&art_url);
+                            if let Some(track_change) = last_track_change.as_mut() {
+                                if has_art(&track_change.track) {
+                                    track_change.track.art_path = Some(art_path.clone());
+                                }
+                            }
+                            let current = self.current_tx.borrow().clone();
+                            if let Some(mut response) = current {
+                                if let Some(track) = response.track.as_mut().filter(|track| has_art(track)) {
+                                    track.art_path = Some(art_path);
+                                    self.send(response);
+                                }
+                            }
+                        }
+                        ChangeEvent::PendingQueued => {
+                            // Also for tracks that aren't playing, otherwise
+                            // nothing replays the queue.
+                            if !self.has_pending {
+                                restart_after(&mut retry_interval, self.backoff.next_delay());
+                            }
+                            self.has_pending = tracker.has_pending();
                         }
                         ChangeEvent::TrackLiked(is_liked) => {
-                            interval = time::interval_at(Instant::now() + Duration::from_secs(2), interval.period());
-                            self.send_and_wake( MonitorResponse {
-                                track: None,
+                            restart_after(&mut interval, self.toast_duration);
+                            let pending = tracker
+                                .current_track_id
+                                .as_ref()
+                                .map(|track_id| tracker.is_pending(track_id))
+                                .unwrap_or_default();
+                            // Keep the track so it can still be shown during
+                            // the message, depending on the toast mode.
+                            let response = last_track_change.as_ref().map_or_else(stopped_response, |track_change| {
+                                track_response(track_change, None, false, false)
+                            });
+                            let track_status = response.track_status();
+                            self.send(MonitorResponse {
                                 status: if is_liked {TrackStatus::Added} else {TrackStatus::Removed}.into(),
                                 is_liked: Some(is_liked),
-                            })?;
+                                pending,
+                                ..response
+                            });
+                            let track = last_track_change.as_ref().map(|track_change| &track_change.track);
+                            if let (Some(notifier), Some(track)) = (&self.notifier, track) {
+                                notifier.liked_changed(track, is_liked);
+                            }
+                            let event = if is_liked {HookEvent::Liked} else {HookEvent::Unliked};
+                            self.run_hook(event, track_status, track, Some(is_liked));
+                        }
+                    }
+                }
+                _ = retry_interval.tick(), if self.offline || self.has_pending => {
+                    let mut tracker = liked_tracker.lock().await;
+                    let result = tracker.replay_pending().await;
+                    self.has_pending = tracker.has_pending();
+                    let result = match (result, last_track_change.clone()) {
+                        (Ok(()), Some(track_change)) => match track_change.track.id.clone().filter(|_| !track_change.track.is_ad) {
+                            Some(track_id) => {
+                                debug!("Retrying to check liked status of {track_id}");
+                                tracker
+                                    .check_liked(track_id, false)
+                                    .await
+                                    .map(|is_liked| Some((track_change, is_liked)))
+                            }
+                            None => Ok(None),
+                        },
+                        (Ok(()), None) => Ok(None),
+                        (Err(err), _) => Err(err),
+                    };
+                    match result {
+                        Ok(update) => {
+                            if self.offline {
+                                info!("Spotify Web API is reachable again");
+                            }
+                            self.offline = false;
+                            self.backoff.reset();
+                            if let Some((track_change, is_liked)) = update {
+                                self.send(track_response(&track_change, Some(is_liked), false, false));
+                            }
+                        }
+                        Err(err) => {
+                            let delay = self.backoff.next_delay_after(&err);
+                            warn!("Could not reach the Spotify Web API, retrying in {delay:?}: {err:#}");
+                            restart_after(&mut retry_interval, delay);
                         }
                     }
                 }
@@ -181,24 +555,46 @@ impl Service {
                     debug!("Interval has passed, updating!");
                     update_requests_tx.send(()).context("Could not request update")?;
                 }
+                Some(()) = shutdown_rx.recv() => {
+                    info!("Shutdown requested, stopping...");
+                    if let Err(err) = systemd::notify("STOPPING=1") {
+                        warn!("{err:#}");
+                    }
+                    dbus_handle.abort();
+                    actions_handle.abort();
+                    shutdown_handle.abort();
+                    self.change_rx.close();
+
+                    // Let monitoring clients know before their streams end,
+                    // this isn't printed by the instance running the server.
+                    let mut last = self.current_tx.borrow().clone().unwrap_or_default();
+                    last.stopping = true;
+                    self.current_tx.send_replace(Some(last));
+                    stop_rpc_tx.send(()).ok();
+                    match timeout(DRAIN_TIMEOUT, &mut rpc_handle).await {
+                        Ok(join_result) => join_result?.context("RPC server failed while stopping")?,
+                        Err(_) => {
+                            warn!("Monitoring clients did not disconnect in time");
+                            rpc_handle.abort();
+                        }
+                    }
+
+                    liked_tracker.lock().await.write_pending()?;
+                    return Ok(());
+                }
                 join_result = &mut dbus_handle => {
                     rpc_handle.abort();
+                    actions_handle.abort();
+                    shutdown_handle.abort();
                     self.change_rx.close();
                     return join_result?.context("DBUS client closed early!");
                 }
                 join_result = &mut rpc_handle => {
                     dbus_handle.abort();
+                    actions_handle.abort();
+                    shutdown_handle.abort();
                     self.change_rx.close();
-                    if let Err(e) = join_result? {
-                        let error_message = e.source().map(|err| err.to_string()).unwrap_or_else(||"".to_string());
-                        let is_address_in_use = error_message.ends_with("(os error 98)");
-
-                        if is_address_in_use {
-                            return self.monitor_client().await;
-                        }
-                        return Err(e).context("RPC server closed early");
-                    }
-                    return Ok(());
+                    return join_result?.context("RPC server closed early");
                 }
                 else => {
                     tokio::time::sleep(Duration::from_millis(5)).await;
@@ -207,3 +603,39 @@ impl Service {
         }
     }
 }
+
+/// The response showing the track with its status.
+fn track_response(
+    track_change: &TrackChange,
+    is_liked: Option<bool>,
+    offline: bool,
+    pending: bool,
+) -> MonitorResponse {
+    MonitorResponse {
+        track: Some(track_change.track.clone()),
+        status: track_change.status.into(),
+        is_liked,
+        offline,
+        pending,
+        stopping: false,
+        track_status: track_change.status.into(),
+    }
+}
+
+/// The response when nothing is playing.
+fn stopped_response() -> MonitorResponse {
+    MonitorResponse {
+        track: None,
+        status: TrackStatus::Stopped.into(),
+        is_liked: None,
+        offline: false,
+        pending: false,
+        stopping: false,
+        track_status: TrackStatus::Stopped.into(),
+    }
+}
+
+/// Make the interval tick next after the delay, keeping its period.
+fn restart_after(interval: &mut Interval, delay: Duration) {
+    *interval = time::interval_at(Instant::now() + delay, interval.period());
+}
@@ -0,0 +1,240 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use rspotify::{
+    clients::{BaseClient, OAuthClient},
+    model::TrackId,
+    scopes, AuthCodePkceSpotify, AuthCodeSpotify, Config, Credentials, OAuth,
+    DEFAULT_CACHE_PATH,
+};
+
+use crate::shared::config::{
+    cache_folder, cache_folder_path, legacy_path, migrate_legacy_file,
+    resolve_home_path, SpotifyConfig,
+};
+
+pub const DEFAULT_REDIRECT_URI: &str = "http://127.0.0.1:8000";
+
+/// Run the same expression for whichever authorization flow is used.
+macro_rules! with_client {
+    ($self:expr, $client:ident => $expr:expr) => {
+        match $self {
+            SpotifyClient::AuthCode($client) => $expr,
+            SpotifyClient::Pkce($client) => $expr,
+        }
+    };
+}
+
+/// The Spotify Web API client.
+///
+/// The regular authorization code flow is used when a client secret is
+/// configured, otherwise PKCE is used which only needs a client ID.
+pub enum SpotifyClient {
+    AuthCode(AuthCodeSpotify),
+    Pkce(AuthCodePkceSpotify),
+}
+
+impl SpotifyClient {
+    /// Create the Spotify client from the config, which is overridden by the
+    /// `RSPOTIFY_*` environment variables.
+    pub fn new(spotify_config: &SpotifyConfig) -> Result<Self> {
+        let cache_path = match &spotify_config.token_cache_path {
+            Some(path) => resolve_home_path(path.clone())?,
+            None => {
+                let path = cache_folder()?.join(DEFAULT_CACHE_PATH);
+                migrate_legacy_file(&path)?;
+                path
+            }
+        };
+        SpotifyClient::with_cache_path(spotify_config, cache_path)
+    }
+
+    /// Same as `new`, but without creating the cache folder or moving the
+    /// token cache out of the legacy folder, used to diagnose problems.
+    pub fn new_read_only(spotify_config: &SpotifyConfig) -> Result<Self> {
+        let cache_path = match &spotify_config.token_cache_path {
+            Some(path) => resolve_home_path(path.clone())?,
+            None => {
+                let path = cache_folder_path()?.join(DEFAULT_CACHE_PATH);
+                let legacy_path = legacy_path(&path)?;
+                // `new` would move it to the cache folder.
+                if !path.exists() && legacy_path.exists() {
+                    legacy_path
+                } else {
+                    path
+                }
+            }
+        };
+        SpotifyClient::with_cache_path(spotify_config, cache_path)
+    }
+
+    fn with_cache_path(
+        spotify_config: &SpotifyConfig,
+        cache_path: PathBuf,
+    ) -> Result<Self> {
+        let oauth = OAuth {
+            redirect_uri: redirect_uri(spotify_config),
+            scopes: scopes!("user-library-read", "user-library-modify"),
+            ..Default::default()
+        };
+        let creds = credentials(spotify_config)?;
+        let config = Config {
+            token_cached: true,
+            token_refreshing: true,
+            cache_path,
+            ..Default::default()
+        };
+        Ok(if creds.secret.is_some() {
+            SpotifyClient::AuthCode(AuthCodeSpotify::with_config(
+                creds, oauth, config,
+            ))
+        } else {
+            SpotifyClient::Pkce(AuthCodePkceSpotify::with_config(
+                creds, oauth, config,
+            ))
+        })
+    }
+
+    pub fn redirect_uri(&self) -> &str {
+        with_client!(self, client => &client.oauth.redirect_uri)
+    }
+
+    pub fn cache_path(&self) -> &Path {
+        with_client!(self, client => &client.config.cache_path)
+    }
+
+    pub fn get_authorize_url(&mut self) -> Result<String> {
+        Ok(match self {
+            SpotifyClient::AuthCode(client) => {
+                client.get_authorize_url(true)?
+            }
+            SpotifyClient::Pkce(client) => client.get_authorize_url(None)?,
+        })
+    }
+
+    pub fn parse_response_code(&self, url: &str) -> Option<String> {
+        with_client!(self, client => client.parse_response_code(url))
+    }
+
+    /// Request a token with the code from the redirect and cache it.
+    pub async fn request_token(&self, code: &str) -> Result<()> {
+        with_client!(self, client => {
+            client.request_token(code).await?;
+            client.write_token_cache().await?;
+        });
+        Ok(())
+    }
+
+    /// Use the cached token from `spotifatius auth`.
+    ///
+    /// Expired tokens are refreshed automatically on the first request.
+    pub async fn load_token(&self) -> Result<()> {
+        let token = with_client!(self, client => client.read_token_cache(true).await)
+            .ok()
+            .flatten()
+            .with_context(|| {
+                format!(
+                    "No Spotify token found at {}, run `spotifatius auth` first",
+                    self.cache_path().display()
+                )
+            })?;
+        let token_lock = with_client!(self, client => client.get_token());
+        *token_lock
+            .lock()
+            .await
+            .map_err(|_| anyhow!("Could not lock the Spotify token"))? =
+            Some(token);
+        Ok(())
+    }
+
+    pub async fn token_expires_at(&self) -> Option<String> {
+        let token_lock = with_client!(self, client => client.get_token());
+        let token = token_lock.lock().await.ok()?;
+        token
+            .as_ref()
+            .and_then(|token| token.expires_at)
+            .map(|expires_at| expires_at.to_rfc3339())
+    }
+
+    pub async fn saved_tracks_add(&self, track_id: &str) -> Result<()> {
+        let track_id = TrackId::from_id(track_id)?;
+        with_client!(self, client => {
+            client.current_user_saved_tracks_add([track_id].into_iter()).await?
+        });
+        Ok(())
+    }
+
+    pub async fn saved_tracks_delete(&self, track_id: &str) -> Result<()> {
+        let track_id = TrackId::from_id(track_id)?;
+        with_client!(self, client => {
+            client.current_user_saved_tracks_delete([track_id].into_iter()).await?
+        });
+        Ok(())
+    }
+
+    pub async fn saved_tracks_contains(&self, track_id: &str) -> Result<bool> {
+        let track_id = TrackId::from_id(track_id)?;
+        let liked = with_client!(self, client => {
+            client.current_user_saved_tracks_contains([track_id].into_iter()).await?
+        });
+        Ok(liked[0])
+    }
+}
+
+fn redirect_uri(spotify_config: &SpotifyConfig) -> String {
+    env::var("RSPOTIFY_REDIRECT_URI")
+        .ok()
+        .or_else(|| spotify_config.redirect_uri.clone())
+        .unwrap_or_else(|| DEFAULT_REDIRECT_URI.to_string())
+}
+
+fn credentials(spotify_config: &SpotifyConfig) -> Result<Credentials> {
+    let id = env::var("RSPOTIFY_CLIENT_ID")
+        .ok()
+        .or_else(|| spotify_config.client_id.clone())
+        .context(
+            "Could not find the client ID in the RSPOTIFY_CLIENT_ID environment variable or the spotify.client_id config",
+        )?;
+    let secret = match env::var("RSPOTIFY_CLIENT_SECRET").ok() {
+        Some(secret) => Some(secret),
+        None => client_secret(spotify_config)?,
+    };
+    Ok(match secret {
+        Some(secret) => Credentials::new(&id, &secret),
+        None => Credentials::new_pkce(&id),
+    })
+}
+
+/// Read the client secret from the configured file or command.
+fn client_secret(spotify_config: &SpotifyConfig) -> Result<Option<String>> {
+    if let Some(path) = &spotify_config.client_secret_file {
+        let path = resolve_home_path(path.clone())?;
+        let secret = fs::read_to_string(&path).with_context(|| {
+            format!("Could not read the client secret from {}", path.display())
+        })?;
+        return Ok(Some(secret.trim().to_string()));
+    }
+    if let Some(command) = &spotify_config.client_secret_command {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .output()
+            .with_context(|| format!("Could not run `{command}`"))?;
+        if !output.status.success() {
+            bail!(
+                "`{command}` failed with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        // Commands like `pass` can print more than the secret.
+        let stdout = String::from_utf8(output.stdout)?;
+        let secret = stdout.lines().next().unwrap_or_default().trim();
+        return Ok(Some(secret.to_string()));
+    }
+    Ok(None)
+}
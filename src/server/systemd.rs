@@ -0,0 +1,66 @@
+use std::{
+    env,
+    net::TcpListener,
+    os::{
+        fd::FromRawFd,
+        linux::net::SocketAddrExt,
+        unix::net::{SocketAddr, UnixDatagram},
+    },
+    process,
+};
+
+use anyhow::{Context, Result};
+use nix::fcntl::{fcntl, FcntlArg, FdFlag};
+use tracing::debug;
+
+/// The first file descriptor passed by systemd socket activation.
+const SD_LISTEN_FDS_START: i32 = 3;
+
+/// Get the listener passed by systemd socket activation, if any.
+pub fn activated_listener() -> Result<Option<TcpListener>> {
+    let is_for_us = env::var("LISTEN_PID")
+        .ok()
+        .and_then(|pid| pid.parse::<u32>().ok())
+        .map_or(false, |pid| pid == process::id());
+    let fds = env::var("LISTEN_FDS")
+        .ok()
+        .and_then(|fds| fds.parse::<i32>().ok())
+        .unwrap_or_default();
+    if !is_for_us || fds < 1 {
+        return Ok(None);
+    }
+    debug!("Using the socket passed by systemd");
+
+    // Make sure the socket is only taken once.
+    env::remove_var("LISTEN_PID");
+    env::remove_var("LISTEN_FDS");
+    // Inherited file descriptors aren't close-on-exec, so hooks would keep
+    // the socket open.
+    fcntl(SD_LISTEN_FDS_START, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))
+        .context("Could not use the socket passed by systemd")?;
+    // Safety: systemd passes ownership of the listening socket to us.
+    let listener = unsafe { TcpListener::from_raw_fd(SD_LISTEN_FDS_START) };
+    listener
+        .set_nonblocking(true)
+        .context("Could not use the socket passed by systemd")?;
+    Ok(Some(listener))
+}
+
+/// Notify systemd of a state change such as `READY=1`.
+///
+/// This does nothing when not started by systemd with `Type=notify`.
+pub fn notify(state: &str) -> Result<()> {
+    let Some(path) = env::var_os("NOTIFY_SOCKET") else {
+        return Ok(());
+    };
+    let path = path.to_string_lossy();
+    let address = match path.strip_prefix('@') {
+        Some(name) => SocketAddr::from_abstract_name(name.as_bytes())?,
+        None => SocketAddr::from_pathname(path.as_ref())?,
+    };
+    let socket = UnixDatagram::unbound()?;
+    socket
+        .send_to_addr(state.as_bytes(), &address)
+        .context("Could not notify systemd")?;
+    Ok(())
+}
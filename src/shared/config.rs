@@ -1,11 +1,34 @@
-use std::{collections::HashMap, env, fs::File, io::Read, path::PathBuf};
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, create_dir_all, File},
+    io::Read,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use anyhow::{Context, Error, Result};
+use anyhow::{bail, Context, Error, Result};
 use serde::Deserialize;
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
-pub const DEFAULT_CONFIG_FOLDER: &str = "~/.config/spotifatius";
-pub const DEFAULT_CONFIG_PATH: &str = "~/.config/spotifatius/config.toml";
+pub const DEFAULT_CONFIG_PATH: &str =
+    "$XDG_CONFIG_HOME/spotifatius/config.toml";
+/// The commented default config written by `config init`.
+pub const DEFAULT_CONFIG: &str = include_str!("default_config.toml");
+pub const CACHE_FOLDER: &str = "$XDG_CACHE_HOME/spotifatius";
+pub const STATE_FOLDER: &str = "$XDG_STATE_HOME/spotifatius";
+/// Where the token cache and other files were stored before following the
+/// XDG base directories.
+const LEGACY_FOLDER: &str = "~/.config/spotifatius";
+
+/// The XDG base directory variables and their default if unset.
+const XDG_BASE_DIRS: [(&str, &str); 4] = [
+    ("XDG_CONFIG_HOME", "~/.config"),
+    ("XDG_CACHE_HOME", "~/.cache"),
+    ("XDG_STATE_HOME", "~/.local/state"),
+    ("XDG_RUNTIME_DIR", "/tmp"),
+];
 
 #[derive(Clone, Debug, Deserialize, Default)]
 pub struct Config {
@@ -15,6 +38,311 @@ pub struct Config {
     pub format: String,
 	#[serde(default)]
     pub text_template: TemplateConfig,
+    #[serde(default)]
+    pub spotify: SpotifyConfig,
+    #[serde(default)]
+    pub monitor: MonitorConfig,
+    /// Maximum number of characters of the output, longer output is
+    /// truncated.
+    pub width: Option<usize>,
+    /// Named overrides selected with `--profile`.
+    #[serde(default)]
+    pub profiles: HashMap<String, ProfileConfig>,
+    #[serde(default)]
+    pub formats: FormatsConfig,
+    #[serde(default)]
+    pub messages: MessagesConfig,
+    #[serde(default)]
+    pub toast: ToastConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub album_art: AlbumArtConfig,
+}
+
+/// Formats per status, `format` is used for tracks without one.
+#[derive(Clone, Debug, Deserialize, Default)]
+pub struct FormatsConfig {
+    pub playing: Option<String>,
+    pub paused: Option<String>,
+    /// Used while an advertisement is playing.
+    pub ad: Option<String>,
+    /// Shown when Spotify is stopped or closed, nothing is shown if empty.
+    pub stopped: Option<String>,
+    /// Shown when a track was liked, `messages.added` if not set.
+    pub added: Option<String>,
+    /// Shown when a track was unliked, `messages.removed` if not set.
+    pub removed: Option<String>,
+}
+
+impl FormatsConfig {
+    /// Set the formats of the other config that are set.
+    fn extend(&mut self, other: FormatsConfig) {
+        let fields = [
+            (&mut self.playing, other.playing),
+            (&mut self.paused, other.paused),
+            (&mut self.ad, other.ad),
+            (&mut self.stopped, other.stopped),
+            (&mut self.added, other.added),
+            (&mut self.removed, other.removed),
+        ];
+        for (field, value) in fields {
+            if value.is_some() {
+                *field = value;
+            }
+        }
+    }
+}
+
+/// Messages printed by the commands and shown in the bar, e.g. to translate
+/// them.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct MessagesConfig {
+    pub added: String,
+    pub removed: String,
+    pub already_added: String,
+    pub not_added: String,
+    /// Appended when Spotify can't be reached and the change is queued.
+    pub pending: String,
+}
+
+impl Default for MessagesConfig {
+    fn default() -> Self {
+        MessagesConfig {
+            added: "Added to library!".to_string(),
+            removed: "Removed from library!".to_string(),
+            already_added: "Already in library.".to_string(),
+            not_added: "Not in library.".to_string(),
+            pending: "It will be synced once Spotify can be reached."
+                .to_string(),
+        }
+    }
+}
+
+/// How the message after liking or unliking the current track is shown.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ToastConfig {
+    /// Number of seconds the message is shown for. Only read when the server
+    /// starts.
+    pub duration: f64,
+    pub mode: ToastMode,
+}
+
+impl ToastConfig {
+    pub fn duration(&self) -> Result<Duration> {
+        Duration::try_from_secs_f64(self.duration).with_context(|| {
+            format!("invalid toast duration: {}", self.duration)
+        })
+    }
+}
+
+impl Default for ToastConfig {
+    fn default() -> Self {
+        ToastConfig {
+            duration: 2.0,
+            mode: ToastMode::Replace,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ToastMode {
+    /// Show the message instead of the track.
+    Replace,
+    /// Show the message after the track.
+    Append,
+    /// Keep showing the track.
+    None,
+}
+
+/// Commands run with `sh -c` by the server when something happens.
+///
+/// The event is passed as JSON on stdin and as `SPOTIFATIUS_*` environment
+/// variables. Only read when the server starts.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// Run when a different track starts.
+    pub track_change: Option<String>,
+    /// Run when Spotify starts playing, is paused or stops.
+    pub status_change: Option<String>,
+    /// Run when the current track was liked.
+    pub liked: Option<String>,
+    /// Run when the current track was unliked.
+    pub unliked: Option<String>,
+    /// Run when Spotify is opened.
+    pub opened: Option<String>,
+    /// Run when Spotify is closed.
+    pub closed: Option<String>,
+    /// Number of seconds after which a hook is killed.
+    pub timeout: f64,
+    /// Maximum number of hooks running at the same time, others wait.
+    pub max_concurrent: NonZeroUsize,
+}
+
+impl HooksConfig {
+    pub fn timeout(&self) -> Result<Duration> {
+        Duration::try_from_secs_f64(self.timeout).with_context(|| {
+            format!("invalid hooks timeout: {}", self.timeout)
+        })
+    }
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        HooksConfig {
+            track_change: None,
+            status_change: None,
+            liked: None,
+            unliked: None,
+            opened: None,
+            closed: None,
+            timeout: 10.0,
+            max_concurrent: NonZeroUsize::new(4).unwrap(),
+        }
+    }
+}
+
+/// Desktop notifications sent by the server, only read when it starts.
+///
+/// The summaries and bodies can use the `{artist}`, `{title}` and `{album}`
+/// placeholders.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    pub enabled: bool,
+    /// Summary when a different track starts.
+    pub summary: String,
+    /// Body when a different track starts.
+    pub body: String,
+    /// Summary after liking or unliking, `{message}` is `messages.added` or
+    /// `messages.removed`.
+    pub liked_summary: String,
+    /// Body after liking or unliking, can use `{message}` as well.
+    pub liked_body: String,
+    /// Milliseconds until the notification expires, `-1` for the default of
+    /// the notification server and `0` to never expire.
+    pub expire_timeout: i32,
+    /// Show the Like or Unlike and Skip buttons.
+    pub actions: bool,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        NotificationsConfig {
+            enabled: false,
+            summary: "{title}".to_string(),
+            body: "{artist} - {album}".to_string(),
+            liked_summary: "{message}".to_string(),
+            liked_body: "{artist} - {title}".to_string(),
+            expire_timeout: -1,
+            actions: true,
+        }
+    }
+}
+
+/// Downloading album art to the cache folder for `{art_path}`, notifications
+/// and hooks. Only read when the server starts.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct AlbumArtConfig {
+    pub enabled: bool,
+    /// Maximum size of the cached images in MiB, the least recently used
+    /// images are removed first.
+    pub max_size_mb: u64,
+}
+
+impl Default for AlbumArtConfig {
+    fn default() -> Self {
+        AlbumArtConfig {
+            enabled: true,
+            max_size_mb: 50,
+        }
+    }
+}
+
+/// Overrides of the output options for a single bar.
+#[derive(Clone, Debug, Deserialize, Default)]
+pub struct ProfileConfig {
+    pub format: Option<String>,
+    #[serde(default)]
+    pub text_template: ProfileTemplateConfig,
+    #[serde(default)]
+    pub polybar: PolybarConfig,
+    pub width: Option<usize>,
+    #[serde(default)]
+    pub formats: FormatsConfig,
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+pub struct ProfileTemplateConfig {
+    pub playing: Option<String>,
+    pub paused: Option<String>,
+    pub liked: Option<String>,
+}
+
+impl Config {
+    /// Apply the overrides of the profile, if any.
+    pub fn with_profile(mut self, profile: Option<&str>) -> Result<Config> {
+        let Some(name) = profile else {
+            return Ok(self);
+        };
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            let mut names: Vec<_> = self.profiles.keys().cloned().collect();
+            names.sort();
+            bail!(
+                "Unknown profile `{name}`, the available profiles are: {}",
+                names.join(", ")
+            );
+        };
+        if let Some(format) = profile.format {
+            self.format = format;
+        }
+        if let Some(playing) = profile.text_template.playing {
+            self.text_template.playing = playing;
+        }
+        if let Some(paused) = profile.text_template.paused {
+            self.text_template.paused = paused;
+        }
+        if let Some(liked) = profile.text_template.liked {
+            self.text_template.liked = liked;
+        }
+        self.polybar.colors.extend(profile.polybar.colors);
+        if profile.width.is_some() {
+            self.width = profile.width;
+        }
+        self.formats.extend(profile.formats);
+        Ok(self)
+    }
+}
+
+/// Spotify Web API credentials and OAuth settings.
+///
+/// The `RSPOTIFY_CLIENT_ID`, `RSPOTIFY_CLIENT_SECRET` and
+/// `RSPOTIFY_REDIRECT_URI` environment variables take precedence.
+#[derive(Clone, Debug, Deserialize, Default)]
+pub struct SpotifyConfig {
+    pub client_id: Option<String>,
+    /// File containing the client secret.
+    pub client_secret_file: Option<PathBuf>,
+    /// Command printing the client secret, e.g. `pass show spotify`.
+    pub client_secret_command: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub token_cache_path: Option<PathBuf>,
+}
+
+/// Behaviour of `monitor` instances mirroring another server.
+#[derive(Clone, Debug, Deserialize, Default)]
+pub struct MonitorConfig {
+    /// Exit when the server is stopped with `spotifatius stop` instead of
+    /// taking over as the server.
+    #[serde(default)]
+    pub exit_on_server_stop: bool,
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -55,7 +383,19 @@ fn default_liked_text() -> String {
     " ".to_string()
 }
 
+/// Expand a leading `~` or XDG base directory variable like
+/// `$XDG_CONFIG_HOME` in the path.
 pub fn resolve_home_path(path: PathBuf) -> Result<PathBuf> {
+    for (var, default) in XDG_BASE_DIRS {
+        if let Ok(relative_path) = path.strip_prefix(format!("${var}")) {
+            let base = match env::var_os(var).map(PathBuf::from) {
+                // Relative paths are invalid according to the spec.
+                Some(base) if base.is_absolute() => base,
+                _ => resolve_home_path(PathBuf::from(default))?,
+            };
+            return Ok(base.join(relative_path));
+        }
+    }
     if path.starts_with("~/") {
         let home_path = env::var_os("HOME")
             .context("could not find the $HOME env var to use for the default config path")?;
@@ -72,6 +412,60 @@ pub fn resolve_home_path(path: PathBuf) -> Result<PathBuf> {
     }
 }
 
+fn create_folder(folder: &str) -> Result<PathBuf> {
+    let folder = resolve_home_path(PathBuf::from(folder))?;
+    create_dir_all(&folder).with_context(|| {
+        format!("Could not create folder {}", folder.display())
+    })?;
+    Ok(folder)
+}
+
+/// The folder for files that can be recreated, like the token cache.
+pub fn cache_folder() -> Result<PathBuf> {
+    create_folder(CACHE_FOLDER)
+}
+
+/// The folder for files that should be kept, like pending liked changes.
+pub fn state_folder() -> Result<PathBuf> {
+    create_folder(STATE_FOLDER)
+}
+
+/// Same as `cache_folder`, but without creating it.
+pub fn cache_folder_path() -> Result<PathBuf> {
+    resolve_home_path(PathBuf::from(CACHE_FOLDER))
+}
+
+/// The path of the file with the same name in the legacy folder.
+pub fn legacy_path(path: &Path) -> Result<PathBuf> {
+    let file_name = path.file_name().with_context(|| {
+        format!("invalid path specified: {}", path.display())
+    })?;
+    Ok(resolve_home_path(PathBuf::from(LEGACY_FOLDER))?.join(file_name))
+}
+
+/// Move the file with the same name from the legacy folder if it only exists
+/// there.
+pub fn migrate_legacy_file(path: &Path) -> Result<()> {
+    let legacy_path = legacy_path(path)?;
+    if path.exists() || !legacy_path.exists() {
+        return Ok(());
+    }
+    // Renaming fails when the folders are on different file systems.
+    if fs::rename(&legacy_path, path).is_err() {
+        fs::copy(&legacy_path, path)
+            .and_then(|_| fs::remove_file(&legacy_path))
+            .with_context(|| {
+                format!(
+                    "Could not move {} to {}",
+                    legacy_path.display(),
+                    path.display()
+                )
+            })?;
+    }
+    info!("Moved {} to {}", legacy_path.display(), path.display());
+    Ok(())
+}
+
 pub fn get_config(config_path: PathBuf) -> Result<Config> {
     let path = resolve_home_path(config_path)?;
     debug!("Using config: {}", path.display());
@@ -0,0 +1,431 @@
+use serde_json::{json, Value};
+
+/// JSON Schema of the config for editor completion and validation.
+///
+/// This needs to be kept in sync with `Config`, `config check` uses it to
+/// find unknown keys.
+pub fn config_schema() -> Value {
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "spotifatius config",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "format": {
+                "type": "string",
+                "description": "Format of the output with the {artist}, {title}, {album}, {separator}, {status}, {liked} and {art_path} placeholders.",
+                "default": "{artist} {separator} {title}"
+            },
+            "text_template": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "playing": {
+                        "type": "string",
+                        "description": "Replaces {status} while playing."
+                    },
+                    "paused": {
+                        "type": "string",
+                        "description": "Replaces {status} while paused."
+                    },
+                    "liked": {
+                        "type": "string",
+                        "description": "Replaces {liked} when the track is liked."
+                    }
+                }
+            },
+            "polybar": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "colors": {
+                        "type": "object",
+                        "description": "Colors per class for `--output-type polybar`, the first matching class is used.",
+                        "additionalProperties": { "type": "string" }
+                    }
+                }
+            },
+            "spotify": {
+                "type": "object",
+                "description": "Overridden by the RSPOTIFY_* environment variables.",
+                "additionalProperties": false,
+                "properties": {
+                    "client_id": { "type": "string" },
+                    "client_secret_file": {
+                        "type": "string",
+                        "description": "File containing the client secret."
+                    },
+                    "client_secret_command": {
+                        "type": "string",
+                        "description": "Command printing the client secret, e.g. `pass show spotify`."
+                    },
+                    "redirect_uri": {
+                        "type": "string",
+                        "default": "http://127.0.0.1:8000"
+                    },
+                    "token_cache_path": { "type": "string" }
+                }
+            },
+            "formats": {
+                "type": "object",
+                "description": "Formats per status, none of them have placeholders except playing, paused and ad.",
+                "additionalProperties": false,
+                "properties": {
+                    "playing": {
+                        "type": "string",
+                        "description": "Format while playing, `format` if not set."
+                    },
+                    "paused": {
+                        "type": "string",
+                        "description": "Format while paused, `format` if not set."
+                    },
+                    "ad": {
+                        "type": "string",
+                        "description": "Format while an advertisement is playing."
+                    },
+                    "stopped": {
+                        "type": "string",
+                        "description": "Shown when Spotify is stopped or closed, nothing is shown if empty."
+                    },
+                    "added": {
+                        "type": "string",
+                        "description": "Shown when a track was liked, `messages.added` if not set."
+                    },
+                    "removed": {
+                        "type": "string",
+                        "description": "Shown when a track was unliked, `messages.removed` if not set."
+                    }
+                }
+            },
+            "messages": {
+                "type": "object",
+                "description": "Messages printed by the commands and shown in the bar.",
+                "additionalProperties": false,
+                "properties": {
+                    "added": { "type": "string", "default": "Added to library!" },
+                    "removed": { "type": "string", "default": "Removed from library!" },
+                    "already_added": { "type": "string", "default": "Already in library." },
+                    "not_added": { "type": "string", "default": "Not in library." },
+                    "pending": {
+                        "type": "string",
+                        "description": "Appended when Spotify can't be reached and the change is queued.",
+                        "default": "It will be synced once Spotify can be reached."
+                    }
+                }
+            },
+            "toast": {
+                "type": "object",
+                "description": "How the message after liking or unliking the current track is shown.",
+                "additionalProperties": false,
+                "properties": {
+                    "duration": {
+                        "type": "number",
+                        "minimum": 0,
+                        "description": "Number of seconds the message is shown for. Only read when the server starts.",
+                        "default": 2
+                    },
+                    "mode": {
+                        "enum": ["replace", "append", "none"],
+                        "description": "Show the message instead of the track, after the track, or not at all.",
+                        "default": "replace"
+                    }
+                }
+            },
+            "hooks": {
+                "type": "object",
+                "description": "Commands run with `sh -c` by the server when something happens. The event is passed as JSON on stdin and as SPOTIFATIUS_* environment variables. Only read when the server starts.",
+                "additionalProperties": false,
+                "properties": {
+                    "track_change": { "type": "string", "description": "Run when a different track starts." },
+                    "status_change": { "type": "string", "description": "Run when Spotify starts playing, is paused or stops." },
+                    "liked": { "type": "string", "description": "Run when the current track was liked." },
+                    "unliked": { "type": "string", "description": "Run when the current track was unliked." },
+                    "opened": { "type": "string", "description": "Run when Spotify is opened." },
+                    "closed": { "type": "string", "description": "Run when Spotify is closed." },
+                    "timeout": {
+                        "type": "number",
+                        "minimum": 0,
+                        "description": "Number of seconds after which a hook is killed.",
+                        "default": 10
+                    },
+                    "max_concurrent": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Maximum number of hooks running at the same time, others wait.",
+                        "default": 4
+                    }
+                }
+            },
+            "notifications": {
+                "type": "object",
+                "description": "Desktop notifications sent by the server. Only read when the server starts.",
+                "additionalProperties": false,
+                "properties": {
+                    "enabled": { "type": "boolean", "default": false },
+                    "summary": {
+                        "type": "string",
+                        "description": "Summary when a different track starts with the {artist}, {title} and {album} placeholders.",
+                        "default": "{title}"
+                    },
+                    "body": {
+                        "type": "string",
+                        "description": "Body when a different track starts with the {artist}, {title} and {album} placeholders.",
+                        "default": "{artist} - {album}"
+                    },
+                    "liked_summary": {
+                        "type": "string",
+                        "description": "Summary after liking or unliking, {message} is messages.added or messages.removed.",
+                        "default": "{message}"
+                    },
+                    "liked_body": {
+                        "type": "string",
+                        "description": "Body after liking or unliking, {message} is messages.added or messages.removed.",
+                        "default": "{artist} - {title}"
+                    },
+                    "expire_timeout": {
+                        "type": "integer",
+                        "minimum": -1,
+                        "description": "Milliseconds until the notification expires, -1 for the default of the notification server and 0 to never expire.",
+                        "default": -1
+                    },
+                    "actions": {
+                        "type": "boolean",
+                        "description": "Show the Like or Unlike and Skip buttons.",
+                        "default": true
+                    }
+                }
+            },
+            "album_art": {
+                "type": "object",
+                "description": "Downloading album art to the cache folder for {art_path}, notifications and hooks. Only read when the server starts.",
+                "additionalProperties": false,
+                "properties": {
+                    "enabled": { "type": "boolean", "default": true },
+                    "max_size_mb": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "Maximum size of the cached images in MiB, the least recently used images are removed first.",
+                        "default": 50
+                    }
+                }
+            },
+            "width": {
+                "type": "integer",
+                "minimum": 0,
+                "description": "Maximum number of characters of the output, longer output is truncated."
+            },
+            "profiles": {
+                "type": "object",
+                "description": "Named overrides of the output options selected with `--profile`.",
+                "additionalProperties": {
+                    "type": "object",
+                    "additionalProperties": false,
+                    "properties": {
+                        "format": { "type": "string" },
+                        "text_template": {
+                            "type": "object",
+                            "additionalProperties": false,
+                            "properties": {
+                                "playing": { "type": "string" },
+                                "paused": { "type": "string" },
+                                "liked": { "type": "string" }
+                            }
+                        },
+                        "polybar": {
+                            "type": "object",
+                            "additionalProperties": false,
+                            "properties": {
+                                "colors": {
+                                    "type": "object",
+                                    "additionalProperties": { "type": "string" }
+                                }
+                            }
+                        },
+                        "width": { "type": "integer", "minimum": 0 },
+                        "formats": {
+                            "type": "object",
+                            "additionalProperties": false,
+                            "properties": {
+                                "playing": { "type": "string" },
+                                "paused": { "type": "string" },
+                                "ad": { "type": "string" },
+                                "stopped": { "type": "string" },
+                                "added": { "type": "string" },
+                                "removed": { "type": "string" }
+                            }
+                        }
+                    }
+                }
+            },
+            "monitor": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "exit_on_server_stop": {
+                        "type": "boolean",
+                        "description": "Exit when the server is stopped with `spotifatius stop` instead of taking over as the server.",
+                        "default": false
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Get the dotted paths of the keys in the config that aren't in the schema.
+pub fn unknown_keys(config: &toml::Value) -> Vec<String> {
+    let mut keys = vec![];
+    find_unknown_keys(config, &config_schema(), "", &mut keys);
+    keys
+}
+
+fn find_unknown_keys(
+    value: &toml::Value,
+    schema: &Value,
+    path: &str,
+    keys: &mut Vec<String>,
+) {
+    let Some(table) = value.as_table() else {
+        return;
+    };
+    let properties = schema.get("properties").and_then(Value::as_object);
+    // Either `false` or the schema of keys that aren't properties.
+    let additional_properties = schema.get("additionalProperties");
+    for (key, value) in table {
+        let key_path = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{path}.{key}")
+        };
+        match (
+            properties.and_then(|properties| properties.get(key)),
+            additional_properties,
+        ) {
+            (Some(schema), _) => {
+                find_unknown_keys(value, schema, &key_path, keys)
+            }
+            (None, Some(Value::Bool(false))) => keys.push(key_path),
+            (None, Some(schema)) => {
+                find_unknown_keys(value, schema, &key_path, keys)
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::config::{Config, DEFAULT_CONFIG};
+
+    /// Every option of `Config`, add new ones here too.
+    const FULL_CONFIG: &str = r##"
+format = "{status}{artist} - {title}"
+width = 40
+
+[text_template]
+playing = "> "
+paused = "|| "
+liked = "+"
+
+[formats]
+playing = "{status}{artist} - {title}"
+paused = "{status}{title}"
+ad = "Advertisement"
+stopped = "Stopped"
+added = "Added"
+removed = "Removed"
+
+[messages]
+added = "Added"
+removed = "Removed"
+already_added = "Already added"
+not_added = "Not added"
+pending = "Pending"
+
+[toast]
+duration = 1.5
+mode = "append"
+
+[hooks]
+track_change = "true"
+status_change = "true"
+liked = "true"
+unliked = "true"
+opened = "true"
+closed = "true"
+timeout = 5.0
+max_concurrent = 2
+
+[notifications]
+enabled = true
+summary = "{title}"
+body = "{artist}"
+liked_summary = "{message}"
+liked_body = "{title}"
+expire_timeout = 3000
+actions = false
+
+[album_art]
+enabled = true
+max_size_mb = 10
+
+[polybar.colors]
+playing = "#ffffff"
+
+[spotify]
+client_id = "id"
+client_secret_file = "~/.config/spotifatius/secret"
+client_secret_command = "pass show spotify"
+redirect_uri = "http://127.0.0.1:8000"
+token_cache_path = "~/.cache/spotifatius/token.json"
+
+[monitor]
+exit_on_server_stop = true
+
+[profiles.laptop]
+format = "{title}"
+width = 20
+
+[profiles.laptop.text_template]
+playing = "> "
+paused = "|| "
+liked = "+"
+
+[profiles.laptop.polybar.colors]
+paused = "#aaaaaa"
+
+[profiles.laptop.formats]
+playing = "{title}"
+paused = "{title}"
+ad = "Ad"
+stopped = ""
+added = "Added"
+removed = "Removed"
+"##;
+
+    #[test]
+    fn default_config_has_no_unknown_keys() {
+        let config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        assert_eq!(unknown_keys(&config), Vec::<String>::new());
+    }
+
+    #[test]
+    fn default_config_deserializes() {
+        toml::from_str::<Config>(DEFAULT_CONFIG).unwrap();
+    }
+
+    #[test]
+    fn finds_unknown_keys() {
+        let config =
+            toml::from_str("unknown = 1\n[polybar]\ncolor = 1\n").unwrap();
+        assert_eq!(unknown_keys(&config), ["polybar.color", "unknown"]);
+    }
+
+    #[test]
+    fn full_config_has_no_unknown_keys() {
+        toml::from_str::<Config>(FULL_CONFIG).unwrap();
+        let config = toml::from_str(FULL_CONFIG).unwrap();
+        assert_eq!(unknown_keys(&config), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,62 @@
+use std::{ffi::OsString, fs, io, path::Path};
+
+use anyhow::{Context, Result};
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
+use tokio::io::unix::AsyncFd;
+
+/// Watches the config file for changes with inotify.
+///
+/// The parent folder is watched instead of the file itself, as editors often
+/// save by renaming a new file over the old one. Symlinks are resolved
+/// first, so the folder of the actual file is watched.
+pub struct ConfigWatcher {
+    inotify: AsyncFd<Inotify>,
+    file_name: OsString,
+}
+
+impl ConfigWatcher {
+    pub fn new(config_path: &Path) -> Result<Self> {
+        let config_path = fs::canonicalize(config_path).with_context(|| {
+            format!("Could not resolve {}", config_path.display())
+        })?;
+        let folder = config_path
+            .parent()
+            .context("The config path has no parent folder")?;
+        let file_name = config_path
+            .file_name()
+            .context("The config path has no file name")?
+            .to_os_string();
+
+        let inotify =
+            Inotify::init(InitFlags::IN_NONBLOCK | InitFlags::IN_CLOEXEC)?;
+        inotify
+            .add_watch(
+                folder,
+                AddWatchFlags::IN_CLOSE_WRITE | AddWatchFlags::IN_MOVED_TO,
+            )
+            .with_context(|| format!("Could not watch {}", folder.display()))?;
+        Ok(ConfigWatcher {
+            inotify: AsyncFd::new(inotify)?,
+            file_name,
+        })
+    }
+
+    /// Wait until the config file was written.
+    pub async fn changed(&mut self) -> Result<()> {
+        loop {
+            let mut guard = self.inotify.readable().await?;
+            let events = match guard.try_io(|inotify| {
+                inotify.get_ref().read_events().map_err(io::Error::from)
+            }) {
+                Ok(events) => events?,
+                Err(_would_block) => continue,
+            };
+            if events
+                .iter()
+                .any(|event| event.name.as_ref() == Some(&self.file_name))
+            {
+                return Ok(());
+            }
+        }
+    }
+}
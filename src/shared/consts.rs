@@ -1 +1,8 @@
 pub const ADDRESS: &str = "[::1]:50052";
+/// Version of `proto/service.proto`, bump it on incompatible changes.
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Exit code used by commands when nothing changed.
+pub const UNCHANGED_EXIT_CODE: i32 = 2;
+/// Exit code used by `is-liked` when the liked state can't be checked, as 1
+/// means it isn't liked.
+pub const IS_LIKED_ERROR_EXIT_CODE: i32 = 3;
@@ -1,2 +1,5 @@
 pub mod config;
+pub mod config_schema;
+pub mod config_watcher;
 pub mod consts;
+pub mod track_id;
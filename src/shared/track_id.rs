@@ -0,0 +1,66 @@
+use anyhow::{bail, Result};
+
+/// Get the Spotify track ID from a URI, URL or ID.
+///
+/// Supported formats are `spotify:track:{ID}`,
+/// `https://open.spotify.com/track/{ID}?si=...` and `{ID}`.
+pub fn parse_track_id(track: &str) -> Result<String> {
+    let track = track.trim();
+    let id = if let Some(id) = track.strip_prefix("spotify:track:") {
+        id
+    } else if track.starts_with("http://") || track.starts_with("https://") {
+        let path = track.split(['?', '#']).next().unwrap_or_default();
+        match path.split_once("/track/") {
+            Some((_, id)) => id.trim_end_matches('/'),
+            None => bail!("'{track}' is not a Spotify track URL"),
+        }
+    } else {
+        track
+    };
+
+    if id.len() != 22 || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        bail!("'{track}' is not a valid Spotify track URI, URL or ID");
+    }
+    Ok(id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "4uLU6hMCjMI75M1A2tKUQC";
+
+    #[test]
+    fn parses_uri() {
+        let track = format!("spotify:track:{ID}");
+        assert_eq!(parse_track_id(&track).unwrap(), ID);
+    }
+
+    #[test]
+    fn parses_url_with_query() {
+        let track = format!("https://open.spotify.com/track/{ID}?si=abc123");
+        assert_eq!(parse_track_id(&track).unwrap(), ID);
+    }
+
+    #[test]
+    fn parses_localized_url() {
+        let track = format!("https://open.spotify.com/intl-de/track/{ID}");
+        assert_eq!(parse_track_id(&track).unwrap(), ID);
+    }
+
+    #[test]
+    fn parses_id() {
+        assert_eq!(parse_track_id(&format!(" {ID}\n")).unwrap(), ID);
+    }
+
+    #[test]
+    fn rejects_wrong_length() {
+        assert!(parse_track_id(&ID[1..]).is_err());
+    }
+
+    #[test]
+    fn rejects_other_urls() {
+        let track = format!("https://open.spotify.com/album/{ID}");
+        assert!(parse_track_id(&track).is_err());
+    }
+}